@@ -23,12 +23,9 @@
         cargoToml = "${self}/Cargo.toml";
         cargoTomlConfig = builtins.fromTOML (builtins.readFile cargoToml);
         nativeBuildInputs = with pkgs; [ 
-          # free, grep, printf, awk are assuemed installed
           pkg-config
           playerctl
-          pulseaudio # pactl
           wayland
-          wireplumber # wpctl
           dbus # bluetooth
         ];
       in
@@ -0,0 +1,137 @@
+use crate::{
+    autohide::AutohideEvent,
+    modules::{Action, ActionContext, CommandAction},
+    Message,
+};
+
+/// Actions which are handled by bar-rs itself instead of a shell
+#[derive(Debug)]
+pub enum BuiltinAction {
+    /// `popup:<module>`: Open or close the popup of a module
+    Popup(String),
+    /// `menu` or `menu:<module>`: Open or close the context menu of the clicked module or of
+    /// another one
+    Menu(Option<String>),
+    /// `toggle:module <module>`: Show or hide a module
+    ToggleModule(String),
+    /// `toggle:group <group>`: Expand or collapse a module group
+    ToggleGroup(String),
+    /// `format:next`: Switch the clicked module to its next format
+    NextFormat,
+    /// `reload`: Reload the config
+    Reload,
+    /// `bar:show`, `bar:hide` or `bar:toggle`
+    Bar(Visibility),
+    /// `bar:reveal`: Reveal the bar for a moment while `autohide` is enabled
+    Reveal,
+    /// `bar:pin`: Keep the bar revealed while `autohide` is enabled, or stop doing so
+    Pin,
+}
+
+/// Used by modules which switch their format by default
+pub static NEXT_FORMAT: BuiltinAction = BuiltinAction::NextFormat;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Visibility {
+    Show,
+    Hide,
+    Toggle,
+}
+
+impl Action for BuiltinAction {
+    fn as_message(&self, ctx: &ActionContext) -> Message {
+        match self {
+            BuiltinAction::Popup(module) => Message::OpenPopup {
+                module: module.clone(),
+                bounds: ctx.bounds,
+            },
+            BuiltinAction::Menu(module) => Message::OpenMenu {
+                module: module.clone(),
+                clicked: ctx.module,
+                bounds: ctx.bounds,
+            },
+            BuiltinAction::ToggleModule(module) => Message::ToggleModule(module.clone()),
+            BuiltinAction::ToggleGroup(group) => {
+                Message::ExpandGroup(group.clone(), Visibility::Toggle)
+            }
+            BuiltinAction::NextFormat => {
+                let id = ctx.module;
+                Message::update(move |reg| reg.get_module_by_id_mut(id).next_format())
+            }
+            BuiltinAction::Reload => Message::ReloadConfig,
+            BuiltinAction::Bar(visibility) => Message::SetBarVisibility(*visibility),
+            BuiltinAction::Reveal => Message::Autohide(AutohideEvent::Reveal),
+            BuiltinAction::Pin => Message::Autohide(AutohideEvent::TogglePin),
+        }
+    }
+}
+
+/// Several actions which are executed one after another
+#[derive(Debug)]
+pub struct ChainAction(Vec<Box<dyn Action>>);
+
+impl Action for ChainAction {
+    fn as_message(&self, ctx: &ActionContext) -> Message {
+        Message::Batch(self.0.iter().map(|a| a.as_message(ctx)).collect())
+    }
+}
+
+/// Parse an action from the config. Actions are separated by `;;`, everything which is not a
+/// built-in action is run as a shell command. A value starting with `sh:` is a single command,
+/// so it may use `;;` itself, e.g. in a `case` statement.
+pub fn parse_action(value: &str) -> Box<dyn Action> {
+    if let Some(cmd) = value.trim().strip_prefix("sh:") {
+        return Box::new(CommandAction(cmd.trim().to_string()));
+    }
+    let mut actions: Vec<Box<dyn Action>> = value
+        .split(";;")
+        .map(str::trim)
+        .filter(|action| !action.is_empty())
+        .filter_map(parse_single)
+        .collect();
+    match actions.len() {
+        1 => actions.pop().unwrap(),
+        _ => Box::new(ChainAction(actions)),
+    }
+}
+
+fn parse_single(action: &str) -> Option<Box<dyn Action>> {
+    let builtin = match action.split_once(':') {
+        // Enforce a shell command, for commands which look like a built-in action
+        Some(("sh", cmd)) => return Some(Box::new(CommandAction(cmd.trim().to_string()))),
+        Some(("popup", module)) if !module.trim().is_empty() => {
+            BuiltinAction::Popup(module.trim().to_string())
+        }
+        Some(("menu", module)) if !module.trim().is_empty() => {
+            BuiltinAction::Menu(Some(module.trim().to_string()))
+        }
+        Some(("toggle", target)) => match target.split_once(' ') {
+            Some(("module", module)) if !module.trim().is_empty() => {
+                BuiltinAction::ToggleModule(module.trim().to_string())
+            }
+            Some(("group", group)) if !group.trim().is_empty() => {
+                BuiltinAction::ToggleGroup(group.trim().to_string())
+            }
+            _ => return invalid(action),
+        },
+        Some(("format", "next")) => BuiltinAction::NextFormat,
+        Some(("bar", "reveal")) => BuiltinAction::Reveal,
+        Some(("bar", "pin")) => BuiltinAction::Pin,
+        Some(("bar", visibility)) => BuiltinAction::Bar(match visibility {
+            "show" => Visibility::Show,
+            "hide" => Visibility::Hide,
+            "toggle" => Visibility::Toggle,
+            _ => return invalid(action),
+        }),
+        Some(("popup" | "menu" | "format", _)) => return invalid(action),
+        _ if action == "reload" => BuiltinAction::Reload,
+        _ if action == "menu" => BuiltinAction::Menu(None),
+        _ => return Some(Box::new(CommandAction(action.to_string()))),
+    };
+    Some(Box::new(builtin))
+}
+
+fn invalid(action: &str) -> Option<Box<dyn Action>> {
+    eprintln!("Invalid action: {action}\nUse `sh:{action}` if this is meant to be a shell command");
+    None
+}
@@ -0,0 +1,335 @@
+use std::time::{Duration, Instant};
+
+use iced::{
+    core::{
+        event, layout, mouse, overlay, renderer,
+        widget::{Operation, Tree},
+        Clipboard, Layout, Shell, Widget,
+    },
+    widget::stack,
+    Background, Border, Color, Element, Event, Length, Rectangle, Size, Vector,
+};
+
+use crate::config::animation_config::{AnimationConfig, Easing};
+
+/// Values which can be animated by [Animated]
+pub trait Interpolate: Clone + PartialEq {
+    /// The value at `t` (from 0 to 1) of the way from `self` to `other`
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Color {
+            r: self.r.interpolate(&other.r, t),
+            g: self.g.interpolate(&other.g, t),
+            b: self.b.interpolate(&other.b, t),
+            a: self.a.interpolate(&other.a, t),
+        }
+    }
+}
+
+/// Text can't be interpolated, so the closer one is taken. It can be cross-faded with
+/// [crossfade] instead.
+impl Interpolate for String {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        match t < 0.5 {
+            true => self.clone(),
+            false => other.clone(),
+        }
+    }
+}
+
+/// A value which moves smoothly to a new target when it is set
+#[derive(Debug)]
+pub struct Animated<T> {
+    from: T,
+    to: T,
+    start: Instant,
+    duration: Duration,
+    easing: Easing,
+}
+
+impl<T: Default + Interpolate> Default for Animated<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Interpolate> Animated<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            from: value.clone(),
+            to: value,
+            start: Instant::now(),
+            duration: Duration::ZERO,
+            easing: Easing::Linear,
+        }
+    }
+
+    /// Use the duration and easing of the `[animations]` section for the following animations
+    pub fn configure(&mut self, config: &AnimationConfig) {
+        self.duration = config.duration();
+        self.easing = config.easing;
+    }
+
+    /// Start moving to a new target, from the value which is shown right now
+    pub fn set(&mut self, value: T) {
+        if value == self.to {
+            return;
+        }
+        self.from = self.value();
+        self.to = value;
+        self.start = Instant::now();
+    }
+
+    /// Jump to a value without animating
+    pub fn reset(&mut self, value: T) {
+        self.from = value.clone();
+        self.to = value;
+    }
+
+    /// The linear progress of the animation, from 0 to 1
+    fn linear_progress(&self) -> f32 {
+        match self.duration.is_zero() {
+            true => 1.,
+            false => (self.start.elapsed().as_secs_f32() / self.duration.as_secs_f32()).min(1.),
+        }
+    }
+
+    /// The eased progress of the animation, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        self.easing.apply(self.linear_progress())
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.linear_progress() < 1.
+    }
+
+    pub fn value(&self) -> T {
+        self.from.interpolate(&self.to, self.progress())
+    }
+
+    /// The value the animation started from
+    pub fn previous(&self) -> &T {
+        &self.from
+    }
+
+    /// The value the animation ends at
+    pub fn target(&self) -> &T {
+        &self.to
+    }
+}
+
+/// Fade from the previous to the current value of `animated`. `view` creates the element of a
+/// value with the given opacity.
+pub fn crossfade<'a, T, Message, F>(animated: &Animated<T>, view: F) -> Element<'a, Message>
+where
+    T: Interpolate,
+    Message: 'a,
+    F: Fn(&T, f32) -> Element<'a, Message>,
+{
+    match animated.is_animating() {
+        true => {
+            let t = animated.progress();
+            // The current value comes first, since it determines the size of the stack
+            stack![
+                view(animated.target(), t),
+                view(animated.previous(), 1. - t)
+            ]
+            .into()
+        }
+        false => view(animated.target(), 1.),
+    }
+}
+
+/// Draws a highlight behind one of the items of a list, which slides to another item when
+/// `position` changes. Fractional positions place the highlight between two items.
+pub struct Highlight<'a, Message, Theme, Renderer> {
+    content: Element<'a, Message, Theme, Renderer>,
+    position: f32,
+    background: Option<Background>,
+    border: Border,
+}
+
+impl<'a, Message, Theme, Renderer> Highlight<'a, Message, Theme, Renderer> {
+    /// The highlight has the bounds of the content of an item, which excludes the margin of the
+    /// container around it
+    pub fn new(
+        content: impl Into<Element<'a, Message, Theme, Renderer>>,
+        position: f32,
+        background: Option<Background>,
+        border: Border,
+    ) -> Self {
+        Self {
+            content: content.into(),
+            position,
+            background,
+            border,
+        }
+    }
+
+    fn bounds(&self, layout: Layout<'_>) -> Option<Rectangle> {
+        let items = layout
+            .children()
+            .map(|item| item.children().next().unwrap_or(item).bounds())
+            .collect::<Vec<_>>();
+        let position = self.position.clamp(0., items.len().checked_sub(1)? as f32);
+        let (from, to) = (
+            items[position.floor() as usize],
+            items[position.ceil() as usize],
+        );
+        let t = position.fract();
+        Some(Rectangle {
+            x: from.x.interpolate(&to.x, t),
+            y: from.y.interpolate(&to.y, t),
+            width: from.width.interpolate(&to.width, t),
+            height: from.height.interpolate(&to.height, t),
+        })
+    }
+}
+
+impl<'a, Message, Theme, Renderer> Widget<Message, Theme, Renderer>
+    for Highlight<'a, Message, Theme, Renderer>
+where
+    Renderer: iced::core::Renderer,
+{
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&mut self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_mut(&mut self.content));
+    }
+
+    fn size(&self) -> Size<Length> {
+        self.content.as_widget().size()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        // The layout of the content is used as is, so that the items of the list stay the
+        // children of the layout of the module
+        self.content
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        if let Some(bounds) = self.bounds(layout) {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds,
+                    border: self.border,
+                    ..Default::default()
+                },
+                self.background
+                    .unwrap_or(Background::Color(Color::TRANSPARENT)),
+            );
+        }
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        );
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, Theme, Renderer>> {
+        self.content
+            .as_widget_mut()
+            .overlay(&mut tree.children[0], layout, renderer, translation)
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<Highlight<'a, Message, Theme, Renderer>>
+    for Element<'a, Message, Theme, Renderer>
+where
+    Message: 'a,
+    Theme: 'a,
+    Renderer: iced::core::Renderer + 'a,
+{
+    fn from(highlight: Highlight<'a, Message, Theme, Renderer>) -> Self {
+        Self::new(highlight)
+    }
+}
@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use configparser::ini::Ini;
+use handlebars::{Handlebars, JsonValue};
+use iced::Color;
+
+use crate::{
+    config::{animation_config::AnimationConfig, parse::StringExt},
+    helpers::{eval_condition, register_condition},
+};
+
+/// Rules which change the background of the bar depending on the state of the compositor, e.g.
+/// a transparent bar on empty workspaces. Rules are listed in the `[appearance]` section and
+/// defined in `[appearance:<name>]` sections, the first rule whose condition is true applies.
+#[derive(Debug)]
+pub struct Appearance {
+    rules: Vec<Rule>,
+    /// How long the background fades to a new color, if it is configured
+    transition: Option<Duration>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    /// The name of the registered condition template
+    condition: String,
+    background: Color,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            rules: vec![],
+            transition: None,
+        }
+    }
+}
+
+impl Appearance {
+    pub fn read(ini: &Ini, templates: &mut Handlebars) -> Self {
+        let default = Self::default();
+        let rules = ini
+            .get("appearance", "rules")
+            .map(|rules| {
+                rules
+                    .split(',')
+                    .map(|rule| rule.trim().to_string())
+                    .filter(|rule| !rule.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        Self {
+            rules: rules
+                .iter()
+                .filter_map(|name| {
+                    let section = format!("appearance:{name}");
+                    let Some(when) = ini.get(&section, "when") else {
+                        eprintln!("The appearance rule {name} has no condition");
+                        return None;
+                    };
+                    let condition = format!("appearance:{name}");
+                    register_condition(&condition, &when, templates)
+                        .map_err(|e| {
+                            eprintln!(
+                                "Failed to parse the condition of appearance rule {name}: {e}"
+                            )
+                        })
+                        .ok()?;
+                    let Some(background) = ini.get(&section, "background").into_color() else {
+                        eprintln!("The appearance rule {name} has no valid background");
+                        return None;
+                    };
+                    Some(Rule {
+                        condition,
+                        background,
+                    })
+                })
+                .collect(),
+            transition: ini
+                .get("appearance", "transition")
+                .and_then(|ms| {
+                    ms.parse()
+                        .map(Duration::from_millis)
+                        .map_err(|e| eprintln!("Invalid transition of the appearance section: {e}"))
+                        .ok()
+                })
+                .or(default.transition),
+        }
+    }
+
+    /// The animation of the background. A configured `transition` is animated even if the
+    /// `[animations]` are disabled.
+    pub fn animation(&self, animations: &AnimationConfig) -> AnimationConfig {
+        match self.transition {
+            Some(duration) => AnimationConfig {
+                enabled: !duration.is_zero(),
+                duration,
+                ..*animations
+            },
+            None => AnimationConfig {
+                duration: Duration::from_millis(300),
+                ..*animations
+            },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The background of the first rule which applies to the values of the modules, see
+    /// [Variables::json](crate::variables::Variables::json)
+    pub fn background(&self, state: &JsonValue, templates: &Handlebars) -> Option<Color> {
+        self.rules
+            .iter()
+            .find(|rule| {
+                eval_condition(&rule.condition, state, templates)
+                    .map_err(|e| eprintln!("Failed to evaluate an appearance rule: {e}"))
+                    .unwrap_or(false)
+            })
+            .map(|rule| rule.background)
+    }
+}
@@ -0,0 +1,105 @@
+use std::time::{Duration, Instant};
+
+use iced::Task;
+use tokio::time::sleep;
+
+use crate::{config::autohide_config::AutohideConfig, Message};
+
+/// Something which may reveal or hide the bar while `autohide` is enabled
+#[derive(Debug, Clone, Copy)]
+pub enum AutohideEvent {
+    /// The pointer entered or left the bar
+    Hover(bool),
+    /// `bar:reveal`: Reveal the bar for `reveal_duration`
+    Reveal,
+    /// `bar:pin`: Keep the bar revealed, or stop doing so
+    TogglePin,
+    /// Apply the state after a delay, if nothing happened in the meantime
+    Check(u64),
+}
+
+#[derive(Debug)]
+pub struct Autohide {
+    /// Whether the bar has its full size
+    pub revealed: bool,
+    hovered: bool,
+    pinned: bool,
+    /// The end of a temporary reveal
+    until: Option<Instant>,
+    /// Incremented by every event, so that outdated checks are ignored
+    generation: u64,
+}
+
+impl Default for Autohide {
+    fn default() -> Self {
+        Self {
+            revealed: true,
+            hovered: false,
+            pinned: false,
+            until: None,
+            generation: 0,
+        }
+    }
+}
+
+impl Autohide {
+    /// Handle an event. Returns whether the bar has to be revealed (`Some(true)`) or hidden
+    /// (`Some(false)`) now, and a task which checks again after a delay.
+    pub fn handle(
+        &mut self,
+        event: AutohideEvent,
+        config: &AutohideConfig,
+        popup_open: bool,
+    ) -> (Option<bool>, Task<Message>) {
+        if !config.enabled {
+            return (None, Task::none());
+        }
+        let now = Instant::now();
+        match event {
+            AutohideEvent::Hover(hovered) => self.hovered = hovered,
+            AutohideEvent::Reveal => self.until = Some(now + config.reveal_duration),
+            AutohideEvent::TogglePin => self.pinned = !self.pinned,
+            AutohideEvent::Check(generation) if generation != self.generation => {
+                return (None, Task::none())
+            }
+            AutohideEvent::Check(_) => {}
+        }
+        self.generation += 1;
+        let reveal = self.wanted(now, popup_open);
+        let changed = reveal != self.revealed;
+        // The pointer has to stay for a moment, explicit reveals happen immediately
+        if changed && matches!(event, AutohideEvent::Hover(_)) {
+            let delay = match reveal {
+                true => config.reveal_delay,
+                false => config.hide_delay,
+            };
+            return (None, self.check_after(delay));
+        }
+        self.revealed = reveal;
+        // Hide the bar again once a temporary reveal ends
+        let task = match self.until {
+            Some(until) if until > now => self.check_after(until - now),
+            _ => Task::none(),
+        };
+        (changed.then_some(reveal), task)
+    }
+
+    /// Hide the bar when autohide is enabled, e.g. after the config is loaded
+    pub fn reset(&mut self, config: &AutohideConfig) {
+        *self = Self {
+            revealed: !config.enabled,
+            ..Default::default()
+        };
+    }
+
+    fn wanted(&self, now: Instant, popup_open: bool) -> bool {
+        self.hovered || self.pinned || popup_open || self.until.is_some_and(|until| until > now)
+    }
+
+    fn check_after(&self, delay: Duration) -> Task<Message> {
+        let generation = self.generation;
+        Task::perform(sleep(delay), move |_| {
+            Message::Autohide(AutohideEvent::Check(generation))
+        })
+    }
+}
@@ -13,9 +13,46 @@ use iced::{
 };
 use iced::{Background, Color, Vector};
 
+/// Pixels of touchpad scrolling which amount to one step of a scroll wheel
+const SCROLL_THRESHOLD: f32 = 30.;
+
+/// An interaction with a [`Button`], passed to its event handler
+#[derive(Debug, Clone)]
+pub struct ButtonEvent {
+    pub event: Event,
+    /// The keyboard modifiers which are held down. These are only known while the bar has keyboard
+    /// focus, see the `kb_focus` option.
+    pub modifiers: keyboard::Modifiers,
+    /// Whether this release of the left mouse button completes a double click
+    pub double_click: bool,
+    /// The direction of a scroll step, if the event is a [`mouse::Event::WheelScrolled`]
+    pub scroll: Option<ScrollDirection>,
+    /// Whether the pointer entered (`Some(true)`) or left (`Some(false)`) the [`Button`]
+    pub hover: Option<bool>,
+}
+
+impl ButtonEvent {
+    /// Whether this is the release of a left click (or of a touch)
+    pub fn is_left_click(&self) -> bool {
+        matches!(
+            self.event,
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                | Event::Touch(touch::Event::FingerLifted { .. })
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
 type EventHandlerFn<'a, Message> = Box<
     dyn Fn(
-            iced::Event,
+            ButtonEvent,
             iced::core::Layout,
             iced::mouse::Cursor,
             &mut dyn iced::core::Clipboard,
@@ -29,7 +66,6 @@ where
     Message: Clone,
 {
     Message(Message),
-    F(EventHandlerFn<'a, Message>),
     FMaybe(EventHandlerFn<'a, Option<Message>>),
 }
 
@@ -37,9 +73,15 @@ impl<Message> ButtonEventHandler<'_, Message>
 where
     Message: Clone,
 {
+    /// Whether the handler might ignore some events, in which case these are left to the widgets
+    /// around the [`Button`]. Only such handlers receive scroll and hover events.
+    fn is_fallible(&self) -> bool {
+        matches!(self, ButtonEventHandler::FMaybe(_))
+    }
+
     fn get(
         &self,
-        event: iced::Event,
+        event: ButtonEvent,
         layout: iced::core::Layout,
         cursor: iced::mouse::Cursor,
         clipboard: &mut dyn iced::core::Clipboard,
@@ -47,7 +89,6 @@ where
     ) -> Option<Message> {
         match self {
             ButtonEventHandler::Message(msg) => Some(msg.clone()),
-            ButtonEventHandler::F(f) => Some(f(event, layout, cursor, clipboard, viewport)),
             ButtonEventHandler::FMaybe(f) => f(event, layout, cursor, clipboard, viewport),
         }
     }
@@ -106,57 +147,43 @@ where
         self
     }
 
-    /// Determines the on_event action of the [`Button`] using a closure
-    pub fn on_event_with<F>(mut self, f: F) -> Self
+    /// Determines the on_event action of the [`Button`] using a closure which might return a
+    /// Message. Unlike the other handlers, this one also receives scroll events.
+    pub fn on_event_try<F>(mut self, f: F) -> Self
     where
         F: Fn(
-                iced::Event,
+                ButtonEvent,
                 iced::core::Layout,
                 iced::mouse::Cursor,
                 &mut dyn iced::core::Clipboard,
                 &Rectangle,
-            ) -> Message
+            ) -> Option<Message>
             + 'a,
     {
-        self.on_event = Some(ButtonEventHandler::F(Box::new(f)));
+        self.on_event = Some(ButtonEventHandler::FMaybe(Box::new(f)));
         self
     }
 
-    /// Determines the on_event action of the [`Button`] with a closure, if Some
-    pub fn on_event_maybe_with<F>(self, f: Option<F>) -> Self
+    /// Determines the on_event action of the [`Button`] with a closure which might return a
+    /// Message, if Some
+    pub fn on_event_maybe_try<F>(self, f: Option<F>) -> Self
     where
         F: Fn(
-                iced::Event,
+                ButtonEvent,
                 iced::core::Layout,
                 iced::mouse::Cursor,
                 &mut dyn iced::core::Clipboard,
                 &Rectangle,
-            ) -> Message
+            ) -> Option<Message>
             + 'a,
     {
         if let Some(f) = f {
-            self.on_event_with(f)
+            self.on_event_try(f)
         } else {
             self
         }
     }
 
-    /// Determines the on_event action of the [`Button`] using a closure which might return a Message
-    pub fn on_event_try<F>(mut self, f: F) -> Self
-    where
-        F: Fn(
-                iced::Event,
-                iced::core::Layout,
-                iced::mouse::Cursor,
-                &mut dyn iced::core::Clipboard,
-                &Rectangle,
-            ) -> Option<Message>
-            + 'a,
-    {
-        self.on_event = Some(ButtonEventHandler::FMaybe(Box::new(f)));
-        self
-    }
-
     /// Sets the width of the [`Button`].
     pub fn width(mut self, width: impl Into<Length>) -> Self {
         self.width = width.into();
@@ -199,11 +226,15 @@ where
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, Default)]
 struct State {
     is_hovered: bool,
     is_pressed: bool,
     is_focused: bool,
+    modifiers: keyboard::Modifiers,
+    last_click: Option<mouse::Click>,
+    /// Scrolling which didn't amount to a full step yet
+    scroll: Vector,
 }
 
 impl<'a, Message, Theme, Renderer> Widget<Message, Theme, Renderer>
@@ -290,10 +321,18 @@ where
             return event::Status::Captured;
         }
 
+        let fallible = self
+            .on_event
+            .as_ref()
+            .is_some_and(ButtonEventHandler::is_fallible);
+
         match event {
-            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
-            | Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Middle))
-            | Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right))
+            Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                tree.state.downcast_mut::<State>().modifiers = modifiers;
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(
+                mouse::Button::Left | mouse::Button::Middle | mouse::Button::Right,
+            ))
             | Event::Touch(touch::Event::FingerPressed { .. }) => {
                 if self.on_event.is_some() {
                     let bounds = layout.bounds();
@@ -303,13 +342,22 @@ where
 
                         state.is_pressed = true;
 
-                        return event::Status::Captured;
+                        if let (Event::Mouse(mouse::Event::ButtonPressed(button)), Some(position)) =
+                            (&event, cursor.position())
+                        {
+                            state.last_click =
+                                Some(mouse::Click::new(position, *button, state.last_click));
+                        }
+
+                        if !fallible {
+                            return event::Status::Captured;
+                        }
                     }
                 }
             }
-            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
-            | Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Middle))
-            | Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Right))
+            Event::Mouse(mouse::Event::ButtonReleased(
+                mouse::Button::Left | mouse::Button::Middle | mouse::Button::Right,
+            ))
             | Event::Touch(touch::Event::FingerLifted { .. }) => {
                 if let Some(on_press) = self.on_event.as_ref() {
                     let state = tree.state.downcast_mut::<State>();
@@ -320,14 +368,66 @@ where
                         let bounds = layout.bounds();
 
                         if cursor.is_over(bounds) {
+                            let event = ButtonEvent {
+                                double_click: matches!(
+                                    event,
+                                    Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+                                ) && state.last_click.is_some_and(|click| {
+                                    matches!(click.kind(), mouse::click::Kind::Double)
+                                }),
+                                modifiers: state.modifiers,
+                                scroll: None,
+                                hover: None,
+                                event,
+                            };
                             if let Some(msg) =
                                 on_press.get(event, layout, cursor, clipboard, viewport)
                             {
                                 shell.publish(msg);
+                                return event::Status::Captured;
                             }
                         }
 
-                        return event::Status::Captured;
+                        if !fallible {
+                            return event::Status::Captured;
+                        }
+                    }
+                }
+            }
+            Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                if let Some(on_scroll) = self.on_event.as_ref().filter(|_| fallible) {
+                    if cursor.is_over(layout.bounds()) {
+                        let state = tree.state.downcast_mut::<State>();
+
+                        state.scroll = state.scroll
+                            + match delta {
+                                mouse::ScrollDelta::Lines { x, y } => {
+                                    Vector::new(x, y) * SCROLL_THRESHOLD
+                                }
+                                mouse::ScrollDelta::Pixels { x, y } => Vector::new(x, y),
+                            };
+
+                        let direction = match state.scroll {
+                            Vector { y, .. } if y >= SCROLL_THRESHOLD => ScrollDirection::Up,
+                            Vector { y, .. } if y <= -SCROLL_THRESHOLD => ScrollDirection::Down,
+                            Vector { x, .. } if x >= SCROLL_THRESHOLD => ScrollDirection::Left,
+                            Vector { x, .. } if x <= -SCROLL_THRESHOLD => ScrollDirection::Right,
+                            _ => return event::Status::Ignored,
+                        };
+                        state.scroll = Vector::ZERO;
+
+                        let event = ButtonEvent {
+                            event,
+                            modifiers: state.modifiers,
+                            double_click: false,
+                            scroll: Some(direction),
+                            hover: None,
+                        };
+                        if let Some(msg) = on_scroll.get(event, layout, cursor, clipboard, viewport)
+                        {
+                            shell.publish(msg);
+                            return event::Status::Captured;
+                        }
                     }
                 }
             }
@@ -338,6 +438,13 @@ where
                         && matches!(key, keyboard::Key::Named(keyboard::key::Named::Enter))
                     {
                         state.is_pressed = true;
+                        let event = ButtonEvent {
+                            event,
+                            modifiers: state.modifiers,
+                            double_click: false,
+                            scroll: None,
+                            hover: None,
+                        };
                         if let Some(msg) = on_press.get(event, layout, cursor, clipboard, viewport)
                         {
                             shell.publish(msg);
@@ -346,11 +453,40 @@ where
                     }
                 }
             }
-            Event::Touch(touch::Event::FingerLost { .. })
-            | Event::Mouse(mouse::Event::CursorLeft) => {
+            Event::Mouse(mouse::Event::CursorMoved { .. } | mouse::Event::CursorLeft) => {
+                let state = tree.state.downcast_mut::<State>();
+                let hovered = cursor.is_over(layout.bounds());
+
+                if let Event::Mouse(mouse::Event::CursorLeft) = event {
+                    state.is_pressed = false;
+                    state.scroll = Vector::ZERO;
+                }
+
+                if hovered != state.is_hovered {
+                    state.is_hovered = hovered;
+
+                    if let Some(on_hover) = self.on_event.as_ref().filter(|_| fallible) {
+                        let event = ButtonEvent {
+                            event,
+                            modifiers: state.modifiers,
+                            double_click: false,
+                            scroll: None,
+                            hover: Some(hovered),
+                        };
+                        // Hover events aren't captured, the widgets around might track the
+                        // pointer as well
+                        if let Some(msg) = on_hover.get(event, layout, cursor, clipboard, viewport)
+                        {
+                            shell.publish(msg);
+                        }
+                    }
+                }
+            }
+            Event::Touch(touch::Event::FingerLost { .. }) => {
                 let state = tree.state.downcast_mut::<State>();
                 state.is_hovered = false;
                 state.is_pressed = false;
+                state.scroll = Vector::ZERO;
             }
             _ => {}
         }
@@ -30,13 +30,14 @@ impl From<BarAnchor> for String {
     }
 }
 
+/// The bar is anchored to the whole edge, so that it stretches along it unless it has a size
 impl From<&BarAnchor> for Anchor {
     fn from(anchor: &BarAnchor) -> Self {
         match anchor {
-            BarAnchor::Top => Anchor::TOP,
-            BarAnchor::Bottom => Anchor::BOTTOM,
-            BarAnchor::Left => Anchor::LEFT,
-            BarAnchor::Right => Anchor::RIGHT,
+            BarAnchor::Top => Anchor::TOP | Anchor::LEFT | Anchor::RIGHT,
+            BarAnchor::Bottom => Anchor::BOTTOM | Anchor::LEFT | Anchor::RIGHT,
+            BarAnchor::Left => Anchor::LEFT | Anchor::TOP | Anchor::BOTTOM,
+            BarAnchor::Right => Anchor::RIGHT | Anchor::TOP | Anchor::BOTTOM,
         }
     }
 }
@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use configparser::ini::Ini;
+
+use super::parse::StringExt;
+
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationConfig {
+    pub enabled: bool,
+    /// How long an animation takes
+    pub duration: Duration,
+    pub easing: Easing,
+}
+
+impl Default for AnimationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            duration: Duration::from_millis(200),
+            easing: Easing::EaseOut,
+        }
+    }
+}
+
+impl AnimationConfig {
+    /// The duration of animations, which is zero if they are disabled
+    pub fn duration(&self) -> Duration {
+        match self.enabled {
+            true => self.duration,
+            false => Duration::ZERO,
+        }
+    }
+}
+
+/// How the progress of an animation is distributed over its duration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    /// Map the linear progress `t` (from 0 to 1) to the eased progress
+    pub fn apply(&self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1. - (1. - t).powi(3),
+            Easing::EaseInOut => match t < 0.5 {
+                true => 4. * t * t * t,
+                false => 1. - (-2. * t + 2.).powi(3) / 2.,
+            },
+        }
+    }
+}
+
+impl From<&Ini> for AnimationConfig {
+    fn from(ini: &Ini) -> Self {
+        let default = Self::default();
+        let section = "animations";
+        Self {
+            enabled: ini
+                .get(section, "enabled")
+                .into_bool()
+                .unwrap_or(default.enabled),
+            duration: ini
+                .get(section, "duration")
+                .and_then(|ms| {
+                    ms.parse()
+                        .map(Duration::from_millis)
+                        .map_err(|e| eprintln!("Invalid duration of the animations section: {e}"))
+                        .ok()
+                })
+                .unwrap_or(default.duration),
+            easing: ini
+                .get(section, "easing")
+                .and_then(|easing| match easing.as_str() {
+                    "linear" => Some(Easing::Linear),
+                    "ease_in" => Some(Easing::EaseIn),
+                    "ease_out" => Some(Easing::EaseOut),
+                    "ease_in_out" => Some(Easing::EaseInOut),
+                    _ => {
+                        eprintln!("Invalid animation easing: {easing}");
+                        None
+                    }
+                })
+                .unwrap_or(default.easing),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use configparser::ini::Ini;
+
+use super::parse::StringExt;
+
+#[derive(Debug, Clone)]
+pub struct AutohideConfig {
+    pub enabled: bool,
+    /// The size of the strip which is left of the bar while it is hidden
+    pub trigger_size: u32,
+    /// How long the pointer has to stay on the strip before the bar is revealed
+    pub reveal_delay: Duration,
+    /// How long the bar stays after the pointer left it
+    pub hide_delay: Duration,
+    /// How long the bar is revealed by `bar:reveal`
+    pub reveal_duration: Duration,
+    /// Variables which reveal the bar when they change, e.g. `volume.volume`
+    pub reveal_on: Vec<String>,
+}
+
+impl Default for AutohideConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            trigger_size: 2,
+            reveal_delay: Duration::from_millis(200),
+            hide_delay: Duration::from_millis(1000),
+            reveal_duration: Duration::from_millis(2000),
+            reveal_on: vec![],
+        }
+    }
+}
+
+impl From<&Ini> for AutohideConfig {
+    fn from(ini: &Ini) -> Self {
+        let default = Self::default();
+        let section = "autohide";
+        let duration = |key: &str| {
+            ini.get(section, key).and_then(|ms| {
+                ms.parse()
+                    .map(Duration::from_millis)
+                    .map_err(|e| eprintln!("Invalid {key} of the autohide section: {e}"))
+                    .ok()
+            })
+        };
+        Self {
+            enabled: ini
+                .get(section, "enabled")
+                .into_bool()
+                .unwrap_or(default.enabled),
+            trigger_size: ini
+                .get(section, "trigger_size")
+                .and_then(|size| size.parse().ok())
+                .unwrap_or(default.trigger_size),
+            reveal_delay: duration("reveal_delay").unwrap_or(default.reveal_delay),
+            hide_delay: duration("hide_delay").unwrap_or(default.hide_delay),
+            reveal_duration: duration("reveal_duration").unwrap_or(default.reveal_duration),
+            reveal_on: ini
+                .get(section, "reveal_on")
+                .map(|variables| {
+                    variables
+                        .split(',')
+                        .map(|v| v.trim().to_string())
+                        .filter(|v| !v.is_empty())
+                        .collect()
+                })
+                .unwrap_or(default.reveal_on),
+        }
+    }
+}
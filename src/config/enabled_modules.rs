@@ -1,55 +1,198 @@
+use std::{collections::HashMap, slice};
+
 use configparser::ini::Ini;
+use iced::Alignment;
+
+use crate::{groups::Group, section::Overflow};
+
+use super::parse::StringExt;
 
 #[derive(Debug)]
 pub struct EnabledModules {
-    pub left: Vec<String>,
-    pub center: Vec<String>,
-    pub right: Vec<String>,
+    /// The sections of the bar, in order
+    pub sections: Vec<SectionConfig>,
+    /// Groups by their name, they are placed in the sections as `group:<name>`
+    pub groups: HashMap<String, Group>,
+}
+
+/// A part of the bar, configured in the `[section:<name>]` section
+#[derive(Debug)]
+pub struct SectionConfig {
+    pub name: String,
+    pub modules: Vec<String>,
+    pub align: Alignment,
+    /// The share of the bar this section takes
+    pub weight: u16,
+    pub overflow: Overflow,
+    /// Overrides the spacing of `[style]`
+    pub spacing: Option<f32>,
+    /// The row (or column, if the bar is vertical) of the section, starting at 0
+    pub row: usize,
+}
+
+impl SectionConfig {
+    fn new(name: &str, modules: Vec<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            modules,
+            align: match name {
+                "center" => Alignment::Center,
+                "right" => Alignment::End,
+                _ => Alignment::Start,
+            },
+            weight: 1,
+            overflow: Overflow::default(),
+            spacing: None,
+            row: 0,
+        }
+    }
+
+    fn read(ini: &Ini, name: &str, modules: Vec<String>) -> Self {
+        let default = Self::new(name, modules);
+        let section = format!("section:{name}");
+        Self {
+            align: ini
+                .get(&section, "align")
+                .and_then(|align| match align.as_str() {
+                    "start" | "left" | "top" => Some(Alignment::Start),
+                    "center" => Some(Alignment::Center),
+                    "end" | "right" | "bottom" => Some(Alignment::End),
+                    _ => {
+                        eprintln!("Invalid alignment of section {name}: {align}");
+                        None
+                    }
+                })
+                .unwrap_or(default.align),
+            weight: ini
+                .get(&section, "weight")
+                .and_then(|weight| {
+                    weight
+                        .parse()
+                        .map_err(|e| eprintln!("Invalid weight of section {name}: {e}"))
+                        .ok()
+                })
+                .unwrap_or(default.weight),
+            overflow: ini
+                .get(&section, "overflow")
+                .and_then(|overflow| match overflow.as_str() {
+                    "clip" => Some(Overflow::Clip),
+                    "shrink" => Some(Overflow::Shrink),
+                    "hide" => Some(Overflow::Hide),
+                    _ => {
+                        eprintln!("Invalid overflow of section {name}: {overflow}");
+                        None
+                    }
+                })
+                .unwrap_or(default.overflow),
+            spacing: ini.get(&section, "spacing").into_float(),
+            row: ini
+                .get(&section, "row")
+                .and_then(|row| match row.parse::<usize>() {
+                    Ok(row @ 1..) => Some(row - 1),
+                    _ => {
+                        eprintln!("Invalid row of section {name}: {row}");
+                        None
+                    }
+                })
+                .unwrap_or(default.row),
+            ..default
+        }
+    }
 }
 
 impl Default for EnabledModules {
     fn default() -> Self {
-        let vec = |list: &[&str]| list.iter().map(|i| i.to_string()).collect();
+        let section = |name: &str, list: &[&str]| {
+            SectionConfig::new(name, list.iter().map(|i| i.to_string()).collect())
+        };
 
         Self {
-            left: vec(&["workspaces", "window"]),
-            center: vec(&["date", "time"]),
-            right: vec(&["media", "volume", "cpu", "memory"]),
+            sections: vec![
+                section("left", &["workspaces", "window"]),
+                section("center", &["date", "time"]),
+                section("right", &["media", "volume", "cpu", "memory"]),
+            ],
+            groups: HashMap::new(),
         }
     }
 }
 
 impl From<&Ini> for EnabledModules {
     fn from(ini: &Ini) -> Self {
-        let get = |field: &str| {
-            ini.get("modules", field).map(|value| {
-                value
-                    .split(',')
-                    .filter(|v| !v.is_empty())
-                    .map(|v| v.trim().to_string())
-                    .collect()
-            })
+        let split = |value: &str| -> Vec<String> {
+            value
+                .split(',')
+                .filter(|v| !v.is_empty())
+                .map(|v| v.trim().to_string())
+                .collect()
         };
+        let get = |field: &str| ini.get("modules", field).map(|value| split(&value));
 
         let default = Self::default();
+        let names = get("sections").unwrap_or_else(|| {
+            default
+                .sections
+                .iter()
+                .map(|section| section.name.clone())
+                .collect()
+        });
 
         Self {
-            left: get("left").unwrap_or(default.left),
-            center: get("center").unwrap_or(default.center),
-            right: get("right").unwrap_or(default.right),
+            sections: names
+                .iter()
+                .map(|name| {
+                    let modules = get(name)
+                        .or_else(|| {
+                            default
+                                .sections
+                                .iter()
+                                .find(|section| section.name == *name)
+                                .map(|section| section.modules.clone())
+                        })
+                        .unwrap_or_default();
+                    SectionConfig::read(ini, name, modules)
+                })
+                .collect(),
+            groups: ini
+                .get_map_ref()
+                .get("modules")
+                .into_iter()
+                .flatten()
+                .filter_map(|(key, value)| Some((key.strip_prefix("group:")?, value.as_ref()?)))
+                .map(|(name, modules)| (name.to_string(), Group::read(ini, name, split(modules))))
+                .collect(),
         }
     }
 }
 
 impl EnabledModules {
+    /// The number of rows of the bar
+    pub fn rows(&self) -> usize {
+        self.sections
+            .iter()
+            .map(|section| section.row + 1)
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// The modules of every section, including those of the groups placed in a section
     pub fn get_all(&self) -> impl Iterator<Item = &String> {
-        self.left
+        self.sections
             .iter()
-            .chain(self.center.iter())
-            .chain(self.right.iter())
+            .flat_map(|section| section.modules.iter())
+            .flat_map(|m| match self.group(m) {
+                Some(group) => group.modules.as_slice(),
+                None if m.starts_with("group:") => Default::default(),
+                None => slice::from_ref(m),
+            })
+    }
+
+    /// The group which is placed as `group:<name>`
+    pub fn group(&self, name: &str) -> Option<&Group> {
+        self.groups.get(name.strip_prefix("group:")?)
     }
 
     pub fn contains(&self, x: &String) -> bool {
-        self.left.contains(x) || self.center.contains(x) || self.right.contains(x)
+        self.get_all().any(|m| m == x)
     }
 }
@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use super::parse::StringExt;
+
+/// Scrolling of text which is longer than `width`, instead of cutting it at `max_length`
+#[derive(Debug, Clone, Copy)]
+pub struct MarqueeConfig {
+    pub enabled: bool,
+    /// The width of the visible part of the text
+    pub width: f32,
+    /// The scroll speed in pixels per second
+    pub speed: f32,
+    /// How long the text stays at either end before it scrolls back
+    pub pause: Duration,
+}
+
+impl Default for MarqueeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            width: 200.,
+            speed: 30.,
+            pause: Duration::from_millis(1500),
+        }
+    }
+}
+
+impl MarqueeConfig {
+    /// Read the `marquee` options, `get` returns the value of the given config key. Returns
+    /// `None` if none of the options is set.
+    pub fn read<F>(get: F, default: &Self) -> Option<Self>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let enabled = get("marquee").into_bool();
+        let width = get("marquee_width").into_float();
+        let speed = get("marquee_speed").into_float();
+        let pause = get("marquee_pause").and_then(|ms| {
+            ms.parse()
+                .map(Duration::from_millis)
+                .map_err(|e| eprintln!("Invalid marquee_pause: {e}"))
+                .ok()
+        });
+        if enabled.is_none() && width.is_none() && speed.is_none() && pause.is_none() {
+            return None;
+        }
+        Some(Self {
+            // Setting any option enables the marquee
+            enabled: enabled.unwrap_or(true),
+            width: width.unwrap_or(default.width),
+            speed: speed.unwrap_or(default.speed),
+            pause: pause.unwrap_or(default.pause),
+        })
+    }
+}
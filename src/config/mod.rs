@@ -1,35 +1,56 @@
 use std::{
     any::TypeId,
+    borrow::Cow,
     collections::{HashMap, HashSet},
     fs::{create_dir_all, File},
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::Arc,
 };
 
 use anchor::BarAnchor;
+use animation_config::AnimationConfig;
+use autohide_config::AutohideConfig;
 use configparser::ini::{Ini, IniDefault};
 use directories::ProjectDirs;
 pub use enabled_modules::EnabledModules;
 use handlebars::Handlebars;
 use iced::{
     futures::{channel::mpsc::Sender, SinkExt},
-    platform_specific::shell::commands::layer_surface::KeyboardInteractivity,
+    platform_specific::shell::commands::layer_surface::{KeyboardInteractivity, Layer},
 };
 use module_config::ModuleConfig;
+use popup_behavior::PopupBehavior;
 use popup_config::PopupConfig;
+use popup_placement::PopupPlacement;
 use tokio::sync::mpsc;
 
-use crate::{registry::Registry, Message};
+use crate::{
+    actions::BuiltinAction,
+    appearance::Appearance,
+    helpers::{register_script_helpers, register_show_when, UnEscapeString},
+    hooks::Hooks,
+    menu::{read_menu, MenuEntry},
+    registry::Registry,
+    template_popup::TemplatePopup,
+    tooltip::register_tooltip,
+    Message,
+};
 pub use thrice::Thrice;
 
 pub mod anchor;
+pub mod animation_config;
+pub mod autohide_config;
 mod enabled_modules;
 mod insets;
+pub mod marquee_config;
 pub mod module_config;
 pub mod parse;
+pub mod popup_behavior;
 pub mod popup_config;
+pub mod popup_placement;
 mod thrice;
+pub mod tooltip_config;
 
 #[derive(Debug)]
 pub struct Config {
@@ -38,9 +59,36 @@ pub struct Config {
     pub enabled_listeners: HashSet<TypeId>,
     pub module_config: ModuleConfig,
     pub popup_config: PopupConfig,
+    /// The behavior of popups, from the `[popup_style]` section
+    pub popup_behavior: PopupBehavior,
+    /// The behavior of the popup of each module, which may be overridden in its
+    /// `[module_popup:<name>]` section
+    pub popup_behaviors: HashMap<String, PopupBehavior>,
+    /// Where popups open, from the `[popup_style]` section
+    pub popup_placement: PopupPlacement,
+    /// The placement of the popup of each module
+    pub popup_placements: HashMap<String, PopupPlacement>,
+    /// Popups defined by a `template`, by the name of their module
+    pub template_popups: HashMap<String, TemplatePopup>,
+    /// The entries of the `[module_menu:<name>]` section of each module which has one
+    pub menus: HashMap<String, Vec<MenuEntry>>,
     pub anchor: BarAnchor,
     pub monitor: Option<String>,
     pub kb_focus: KeyboardInteractivity,
+    pub layer: Layer,
+    /// The layer of the bar while a window is fullscreen, `None` keeps [Config::layer]
+    pub fullscreen_layer: Option<Layer>,
+    pub exclusive_zone: ExclusiveZone,
+    pub autohide: AutohideConfig,
+    pub animation: AnimationConfig,
+    /// Modules which have a `show_when` rule
+    pub show_when: HashSet<String>,
+    /// Modules which have a `tooltip`
+    pub tooltips: HashSet<String>,
+    /// The `priority` of modules, used by sections which hide modules that don't fit
+    pub priorities: HashMap<String, i32>,
+    pub hooks: Hooks,
+    pub appearance: Appearance,
 }
 
 impl Config {
@@ -62,20 +110,67 @@ impl Config {
             enabled_modules,
             module_config: ModuleConfig::default(),
             popup_config: PopupConfig::default(),
+            popup_behavior: PopupBehavior::default(),
+            popup_behaviors: HashMap::new(),
+            popup_placement: PopupPlacement::default(),
+            popup_placements: HashMap::new(),
+            template_popups: HashMap::new(),
+            menus: HashMap::new(),
             anchor: BarAnchor::default(),
             monitor: None,
             kb_focus: KeyboardInteractivity::None,
+            layer: Layer::Top,
+            fullscreen_layer: Some(Layer::Bottom),
+            exclusive_zone: ExclusiveZone::Auto,
+            autohide: AutohideConfig::default(),
+            animation: AnimationConfig::default(),
+            show_when: HashSet::new(),
+            tooltips: HashSet::new(),
+            priorities: HashMap::new(),
+            hooks: Hooks::default(),
+            appearance: Appearance::default(),
         }
     }
 
-    pub fn exclusive_zone(&self) -> i32 {
-        (match self.anchor {
-            BarAnchor::Left | BarAnchor::Right => self.module_config.global.width.unwrap_or(30),
-            BarAnchor::Top | BarAnchor::Bottom => self.module_config.global.height.unwrap_or(30),
-        }) as i32
+    /// The behavior of the popup of the given module
+    pub fn popup_behavior(&self, module: &str) -> PopupBehavior {
+        self.popup_behaviors
+            .get(module)
+            .copied()
+            .unwrap_or(self.popup_behavior)
+    }
+
+    /// The placement of the popup of the given module
+    pub fn popup_placement(&self, module: &str) -> PopupPlacement {
+        self.popup_placements
+            .get(module)
+            .copied()
+            .unwrap_or(self.popup_placement)
+    }
+
+    /// The space reserved by a bar of the given thickness, which is `None` while the bar is
+    /// hidden by `autohide`
+    pub fn exclusive_zone(&self, thickness: Option<u32>) -> i32 {
+        match (self.exclusive_zone, thickness) {
+            (ExclusiveZone::Auto, Some(thickness)) => thickness as i32,
+            // A negative zone doesn't reserve space, it only tells the compositor to ignore
+            // the zones of other surfaces
+            (ExclusiveZone::Custom(zone), thickness) if thickness.is_some() || zone < 0 => zone,
+            _ => 0,
+        }
     }
 }
 
+/// The space the bar reserves at its edge, so that windows don't cover it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExclusiveZone {
+    /// The thickness of the bar
+    Auto,
+    /// The bar is drawn above or below windows
+    None,
+    Custom(i32),
+}
+
 pub fn get_config_dir() -> PathBuf {
     let config_dir = ProjectDirs::from("fun.killarchive", "faervan", "bar-rs")
         .map(|dirs| dirs.config_local_dir().to_path_buf())
@@ -104,12 +199,22 @@ pub fn read_config(path: &PathBuf, registry: &mut Registry, templates: &mut Hand
     let mut defaults = IniDefault::default();
     defaults.delimiters = vec!['='];
     ini.load_defaults(defaults);
+    // Values may contain `;` and `#`, e.g. chained actions and handlebars blocks
+    ini.set_inline_comment_symbols(Some(&[]));
     let Ok(_) = ini.load(path) else {
         eprintln!("Failed to read config from {}", path.to_string_lossy());
         return Config::default(registry);
     };
-    let config: Config = (&ini, &*registry).into();
+    let mut config: Config = (&ini, &*registry).into();
+    register_script_helpers(&ini, path.parent().unwrap_or(Path::new("")), templates);
     let empty_config = HashMap::new();
+    let mut show_when = HashSet::new();
+    let mut tooltips = HashSet::new();
+    let mut priorities = HashMap::new();
+    let mut popup_behaviors = HashMap::new();
+    let mut popup_placements = HashMap::new();
+    let mut template_popups = HashMap::new();
+    let mut menus = HashMap::new();
     registry
         .get_modules_mut(config.enabled_modules.get_all(), &config)
         .map(|m| {
@@ -122,9 +227,102 @@ pub fn read_config(path: &PathBuf, registry: &mut Registry, templates: &mut Hand
                 .get_map_ref()
                 .get(&format!("module_popup:{}", name))
                 .unwrap_or(&empty_config);
-            (m, cfg_map, popup_cfg_map)
+            let menu = ini
+                .get_map_ref()
+                .get(&format!("module_menu:{}", name))
+                .map(|menu| read_menu(&name, menu))
+                .unwrap_or_default();
+            // A configured menu opens on a right click, and a template popup of a module without a
+            // popup of its own on a left click, unless these are used for something else
+            let mut defaults = vec![];
+            if !menu.is_empty() {
+                defaults.push(("on_right_click", "menu".to_string()));
+            }
+            if popup_cfg_map.contains_key("template")
+                && m.popup_size(&config.popup_config).is_none()
+            {
+                defaults.push(("on_click", format!("popup:{name}")));
+            }
+            defaults.retain(|(key, _)| !cfg_map.contains_key(*key));
+            let cfg_map = match defaults.is_empty() {
+                true => Cow::Borrowed(cfg_map),
+                false => {
+                    let mut cfg_map = cfg_map.clone();
+                    cfg_map.extend(
+                        defaults
+                            .into_iter()
+                            .map(|(key, action)| (key.to_string(), Some(action))),
+                    );
+                    Cow::Owned(cfg_map)
+                }
+            };
+            (m, cfg_map, popup_cfg_map, menu)
         })
-        .for_each(|(m, cfg_map, popup_cfg_map)| m.read_config(cfg_map, popup_cfg_map, templates));
+        .for_each(|(m, cfg_map, popup_cfg_map, menu)| {
+            if let Some(Some(rule)) = cfg_map.get("show_when") {
+                if register_show_when(&m.name(), rule, templates) {
+                    show_when.insert(m.name());
+                }
+            }
+            if let Some(tooltip) = cfg_map.get("tooltip").unescape() {
+                if register_tooltip(&m.name(), tooltip, templates) {
+                    tooltips.insert(m.name());
+                }
+            }
+            if let Some(Some(priority)) = cfg_map.get("priority") {
+                match priority.parse() {
+                    Ok(priority) => {
+                        priorities.insert(m.name(), priority);
+                    }
+                    Err(e) => eprintln!("Invalid priority of {}: {e}", m.name()),
+                }
+            }
+            popup_behaviors.insert(
+                m.name(),
+                PopupBehavior::read(
+                    |key| popup_cfg_map.get(key).cloned().flatten(),
+                    &config.popup_behavior,
+                ),
+            );
+            popup_placements.insert(
+                m.name(),
+                PopupPlacement::read(
+                    |key| popup_cfg_map.get(key).cloned().flatten(),
+                    &config.popup_placement,
+                ),
+            );
+            if let Some(popup) = TemplatePopup::read(&m.name(), popup_cfg_map, templates) {
+                template_popups.insert(m.name(), popup);
+            }
+            if !menu.is_empty() {
+                menus.insert(m.name(), menu);
+            }
+            m.read_config(&cfg_map, popup_cfg_map, templates);
+            m.set_animation(&config.animation);
+        });
+    config.show_when = show_when;
+    config.tooltips = tooltips;
+    config.priorities = priorities;
+    config.popup_behaviors = popup_behaviors;
+    config.popup_placements = popup_placements;
+    config.template_popups = template_popups;
+    config.menus = menus;
+    // The keys of hooks contain variables and conditions, which have to keep their case
+    let mut hooks_ini = Ini::new_cs();
+    hooks_ini.load_defaults(IniDefault {
+        case_sensitive: true,
+        ..ini.defaults()
+    });
+    config.hooks = match hooks_ini.load(path) {
+        Ok(map) => Hooks::read(map.get("hooks"), templates),
+        Err(_) => Hooks::default(),
+    };
+    for variable in &config.autohide.reveal_on {
+        config
+            .hooks
+            .watch(variable.clone(), Box::new(BuiltinAction::Reveal));
+    }
+    config.appearance = Appearance::read(&ini, templates);
     config
 }
 
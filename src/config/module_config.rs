@@ -8,7 +8,9 @@ use iced::{
 
 use crate::modules::OnClickAction;
 
-use super::{parse::StringExt, Thrice};
+use super::{
+    marquee_config::MarqueeConfig, parse::StringExt, tooltip_config::TooltipConfig, Thrice,
+};
 
 #[derive(Debug, Default)]
 pub struct ModuleConfig {
@@ -24,6 +26,9 @@ pub struct GlobalModuleConfig {
     pub margin: IcedMargin,
     pub padding: Padding,
     pub background_color: Color,
+    /// Whether the thickness of the bar (its height, or width if it is vertical) is `auto`,
+    /// which sizes it to fit its modules
+    pub auto_thickness: bool,
 }
 
 impl Default for GlobalModuleConfig {
@@ -35,6 +40,7 @@ impl Default for GlobalModuleConfig {
             margin: IcedMargin::default(),
             padding: Padding::default(),
             background_color: Color::from_rgba(0., 0., 0., 0.5),
+            auto_thickness: false,
         }
     }
 }
@@ -53,6 +59,11 @@ pub struct LocalModuleConfig {
     pub background: Option<Background>,
     pub border: Border,
     pub action: OnClickAction,
+    /// The style of tooltips, from the `[tooltip_style]` section
+    pub tooltip: TooltipConfig,
+    /// Whether text is rotated by 90 degrees on vertical bars
+    pub rotate_text: bool,
+    pub marquee: MarqueeConfig,
 }
 
 impl Default for LocalModuleConfig {
@@ -70,10 +81,33 @@ impl Default for LocalModuleConfig {
             background: None,
             border: Border::default(),
             action: OnClickAction::default(),
+            tooltip: TooltipConfig::default(),
+            rotate_text: false,
+            marquee: MarqueeConfig::default(),
         }
     }
 }
 
+impl LocalModuleConfig {
+    /// An estimate of the size a module needs across the bar, based on its font and icon size,
+    /// margins and padding
+    pub fn thickness(&self, cfg_override: &ModuleConfigOverride, vertical: bool) -> f32 {
+        let cross = |padding: Padding| match vertical {
+            true => padding.horizontal(),
+            false => padding.vertical(),
+        };
+        // The default line height of iced
+        let line = |size: f32| size * 1.3;
+        let text = line(cfg_override.font_size.unwrap_or(self.font_size))
+            + cross(cfg_override.text_margin.unwrap_or(self.text_margin));
+        let icon = line(cfg_override.icon_size.unwrap_or(self.icon_size))
+            + cross(cfg_override.icon_margin.unwrap_or(self.icon_margin));
+        text.max(icon)
+            + cross(cfg_override.padding.unwrap_or(self.padding))
+            + cross(cfg_override.margin.unwrap_or(self.margin))
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct ModuleConfigOverride {
     pub text_color: Option<Color>,
@@ -88,6 +122,8 @@ pub struct ModuleConfigOverride {
     pub background: Option<Option<Background>>,
     pub border: Option<Border>,
     pub action: Option<OnClickAction>,
+    pub rotate_text: Option<bool>,
+    pub marquee: Option<MarqueeConfig>,
 }
 
 impl From<&HashMap<String, Option<String>>> for ModuleConfigOverride {
@@ -128,25 +164,14 @@ impl From<&HashMap<String, Option<String>>> for ModuleConfigOverride {
                 }
             },
             action: {
-                let left = map
-                    .get("on_click")
-                    .and_then(|s| s.as_ref().map(|s| s.into()));
-                let center = map
-                    .get("on_middle_click")
-                    .and_then(|s| s.as_ref().map(|s| s.into()));
-                let right = map
-                    .get("on_right_click")
-                    .and_then(|s| s.as_ref().map(|s| s.into()));
-                if left.is_some() || center.is_some() || right.is_some() {
-                    Some(OnClickAction {
-                        left,
-                        center,
-                        right,
-                    })
-                } else {
-                    None
-                }
+                let action = read_actions(|key| map.get(key).cloned().flatten());
+                (!action.is_empty()).then_some(action)
             },
+            rotate_text: map.get("rotate_text").and_then(|s| s.into_bool()),
+            marquee: MarqueeConfig::read(
+                |key| map.get(key).cloned().flatten(),
+                &MarqueeConfig::default(),
+            ),
         }
     }
 }
@@ -169,6 +194,30 @@ impl From<&Ini> for ModuleConfig {
                     .unwrap_or(global.spacing),
                 height: ini.get(section, "height").and_then(|v| v.parse().ok()),
                 width: ini.get(section, "width").and_then(|v| v.parse().ok()),
+                auto_thickness: {
+                    let vertical = ini
+                        .get("general", "anchor")
+                        .into_anchor()
+                        .unwrap_or_default()
+                        .vertical();
+                    let key = match vertical {
+                        true => "width",
+                        false => "height",
+                    };
+                    let auto = ini.get(section, key).is_some_and(|v| v == "auto");
+                    // The width of text which isn't rotated is not measured, it would be clipped
+                    let rotated = ini
+                        .get(module_section, "rotate_text")
+                        .into_bool()
+                        .unwrap_or(local.rotate_text);
+                    if auto && vertical && !rotated {
+                        eprintln!(
+                            "width = auto requires rotate_text = true on vertical bars, \
+                            using the default width"
+                        );
+                    }
+                    auto && (!vertical || rotated)
+                },
                 margin: ini
                     .get(section, "margin")
                     .into_insets()
@@ -242,21 +291,35 @@ impl From<&Ini> for ModuleConfig {
                         radius,
                     }
                 },
-                action: {
-                    let left = ini.get(module_section, "on_click").map(|s| (&s).into());
-                    let center = ini
-                        .get(module_section, "on_middle_click")
-                        .map(|s| (&s).into());
-                    let right = ini
-                        .get(module_section, "on_right_click")
-                        .map(|s| (&s).into());
-                    OnClickAction {
-                        left,
-                        center,
-                        right,
-                    }
-                },
+                action: read_actions(|key| ini.get(module_section, key)),
+                tooltip: ini.into(),
+                rotate_text: ini
+                    .get(module_section, "rotate_text")
+                    .into_bool()
+                    .unwrap_or(local.rotate_text),
+                marquee: MarqueeConfig::read(|key| ini.get(module_section, key), &local.marquee)
+                    .unwrap_or(local.marquee),
             },
         }
     }
 }
+
+/// Read the actions of a module, `get` returns the value of the given config key
+fn read_actions<F>(get: F) -> OnClickAction
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let action = |key: &str| get(key).map(|s| (&s).into());
+    OnClickAction {
+        left: action("on_click"),
+        center: action("on_middle_click"),
+        right: action("on_right_click"),
+        double: action("on_double_click"),
+        shift_left: action("on_shift_click"),
+        ctrl_left: action("on_ctrl_click"),
+        scroll_up: action("on_scroll_up"),
+        scroll_down: action("on_scroll_down"),
+        scroll_left: action("on_scroll_left"),
+        scroll_right: action("on_scroll_right"),
+    }
+}
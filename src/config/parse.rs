@@ -1,11 +1,15 @@
 use configparser::ini::Ini;
 use iced::{
-    platform_specific::shell::commands::layer_surface::KeyboardInteractivity, Background, Color,
+    platform_specific::shell::commands::layer_surface::{KeyboardInteractivity, Layer},
+    Background, Color,
 };
 
 use crate::{registry::Registry, OptionExt};
 
-use super::{anchor::BarAnchor, insets::Insets, Config, Thrice};
+use super::{
+    anchor::BarAnchor, insets::Insets, popup_behavior::PopupBehavior,
+    popup_placement::PopupPlacement, Config, ExclusiveZone, Thrice,
+};
 
 impl From<(&Ini, &Registry)> for Config {
     fn from((ini, registry): (&Ini, &Registry)) -> Self {
@@ -36,6 +40,18 @@ impl From<(&Ini, &Registry)> for Config {
             enabled_modules,
             module_config: ini.into(),
             popup_config: ini.into(),
+            popup_behavior: PopupBehavior::read(
+                |key| ini.get("popup_style", key),
+                &default.popup_behavior,
+            ),
+            popup_behaviors: default.popup_behaviors,
+            popup_placement: PopupPlacement::read(
+                |key| ini.get("popup_style", key),
+                &default.popup_placement,
+            ),
+            popup_placements: default.popup_placements,
+            template_popups: default.template_popups,
+            menus: default.menus,
             anchor: ini
                 .get("general", "anchor")
                 .into_anchor()
@@ -45,6 +61,25 @@ impl From<(&Ini, &Registry)> for Config {
                 .get("general", "kb_focus")
                 .into_kb_focus()
                 .unwrap_or(default.kb_focus),
+            layer: ini
+                .get("general", "layer")
+                .into_layer()
+                .unwrap_or(default.layer),
+            fullscreen_layer: match ini.get("general", "fullscreen_layer") {
+                Some(layer) if layer == "none" => None,
+                layer => layer.into_layer().or(default.fullscreen_layer),
+            },
+            exclusive_zone: ini
+                .get("general", "exclusive_zone")
+                .into_exclusive_zone()
+                .unwrap_or(default.exclusive_zone),
+            autohide: ini.into(),
+            animation: ini.into(),
+            show_when: default.show_when,
+            tooltips: default.tooltips,
+            priorities: default.priorities,
+            hooks: default.hooks,
+            appearance: default.appearance,
         }
     }
 }
@@ -58,6 +93,8 @@ pub trait StringExt {
     fn into_insets(self) -> Option<Insets>;
     fn into_background(self) -> Option<Background>;
     fn into_kb_focus(self) -> Option<KeyboardInteractivity>;
+    fn into_layer(self) -> Option<Layer>;
+    fn into_exclusive_zone(self) -> Option<ExclusiveZone>;
 }
 
 impl StringExt for &Option<String> {
@@ -132,6 +169,29 @@ impl StringExt for &Option<String> {
             _ => None,
         })
     }
+    fn into_layer(self) -> Option<Layer> {
+        self.as_ref().and_then(|v| match v.as_str() {
+            "background" => Some(Layer::Background),
+            "bottom" => Some(Layer::Bottom),
+            "top" => Some(Layer::Top),
+            "overlay" => Some(Layer::Overlay),
+            _ => {
+                eprintln!("Invalid layer: {v}");
+                None
+            }
+        })
+    }
+    fn into_exclusive_zone(self) -> Option<ExclusiveZone> {
+        self.as_ref().and_then(|v| match v.as_str() {
+            "auto" => Some(ExclusiveZone::Auto),
+            "none" => Some(ExclusiveZone::None),
+            zone => zone
+                .parse()
+                .map(ExclusiveZone::Custom)
+                .map_err(|e| eprintln!("Invalid exclusive_zone: {e}"))
+                .ok(),
+        })
+    }
 }
 
 pub trait IntoExt<T> {
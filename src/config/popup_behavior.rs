@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use super::parse::StringExt;
+
+/// When popups open and close, from the `[popup_style]` section and the `[module_popup:<name>]`
+/// section of each module
+#[derive(Debug, Clone, Copy)]
+pub struct PopupBehavior {
+    /// Whether the popup opens while the pointer is over its module and closes after the pointer
+    /// left both, instead of opening on click
+    pub open_on_hover: bool,
+    /// Whether the popup stays open until its module is clicked again, next to other popups
+    pub pinned: bool,
+    /// Whether the popup closes when it loses the keyboard focus
+    pub close_on_unfocus: bool,
+    /// How long the popup stays open while the pointer isn't over it
+    pub timeout: Option<Duration>,
+}
+
+impl Default for PopupBehavior {
+    fn default() -> Self {
+        Self {
+            open_on_hover: false,
+            pinned: false,
+            close_on_unfocus: true,
+            timeout: None,
+        }
+    }
+}
+
+impl PopupBehavior {
+    /// Read the behavior of a popup, `get` returns the value of the given config key. Options
+    /// which aren't set are taken from `default`.
+    pub fn read<F>(get: F, default: &Self) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        Self {
+            open_on_hover: get("open_on")
+                .and_then(|open_on| match open_on.as_str() {
+                    "click" => Some(false),
+                    "hover" => Some(true),
+                    _ => {
+                        eprintln!("Invalid open_on value of a popup: {open_on}");
+                        None
+                    }
+                })
+                .unwrap_or(default.open_on_hover),
+            pinned: get("pinned").into_bool().unwrap_or(default.pinned),
+            close_on_unfocus: get("close_on_unfocus")
+                .into_bool()
+                .unwrap_or(default.close_on_unfocus),
+            // A timeout of 0 keeps the popup open
+            timeout: match get("timeout").map(|ms| ms.parse()) {
+                Some(Ok(0)) => None,
+                Some(Ok(ms)) => Some(Duration::from_millis(ms)),
+                Some(Err(e)) => {
+                    eprintln!("Invalid timeout of a popup: {e}");
+                    default.timeout
+                }
+                None => default.timeout,
+            },
+        }
+    }
+
+    /// Whether the popup takes the keyboard and pointer grab, which closes it when the user
+    /// clicks somewhere else. Pinned and hover popups don't.
+    pub fn grab(&self) -> bool {
+        !self.pinned && !self.open_on_hover
+    }
+}
@@ -5,7 +5,7 @@ use iced::{Background, Border, Color, Padding};
 
 use super::parse::StringExt;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PopupConfig {
     pub width: i32,
     pub height: i32,
@@ -48,6 +48,22 @@ impl Default for PopupConfig {
     }
 }
 
+impl PopupConfig {
+    /// A copy with all colors scaled by the given opacity, used to fade popups in and out
+    pub fn faded(&self, alpha: f32) -> Self {
+        Self {
+            text_color: self.text_color.scale_alpha(alpha),
+            icon_color: self.icon_color.scale_alpha(alpha),
+            background: self.background.scale_alpha(alpha),
+            border: Border {
+                color: self.border.color.scale_alpha(alpha),
+                ..self.border
+            },
+            ..self.clone()
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct PopupConfigOverride {
     pub width: Option<i32>,
@@ -0,0 +1,84 @@
+use iced::Alignment;
+
+/// The `constraint_adjustment` flags of an xdg positioner, for both axes
+const SLIDE: u32 = 1 | 2;
+const FLIP: u32 = 4 | 8;
+const RESIZE: u32 = 16 | 32;
+
+/// Where a popup opens next to its module, from the `[popup_style]` section and the
+/// `[module_popup:<name>]` section of each module
+#[derive(Debug, Clone, Copy)]
+pub struct PopupPlacement {
+    /// The alignment of the popup along the bar, relative to its module
+    pub align: Alignment,
+    /// Moves the popup by these pixels to the right
+    pub offset_x: i32,
+    /// Moves the popup by these pixels down
+    pub offset_y: i32,
+    /// How the compositor may move the popup if it doesn't fit on the output, the
+    /// `constraint_adjustment` of the positioner
+    pub screen_edge: u32,
+}
+
+impl Default for PopupPlacement {
+    fn default() -> Self {
+        Self {
+            align: Alignment::Start,
+            offset_x: 0,
+            offset_y: 0,
+            screen_edge: FLIP | SLIDE,
+        }
+    }
+}
+
+impl PopupPlacement {
+    /// Read the placement of a popup, `get` returns the value of the given config key. Options
+    /// which aren't set are taken from `default`.
+    pub fn read<F>(get: F, default: &Self) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let offset = |key: &str, default: i32| {
+            get(key)
+                .and_then(|offset| {
+                    offset
+                        .parse()
+                        .map_err(|e| eprintln!("Invalid {key} of a popup: {e}"))
+                        .ok()
+                })
+                .unwrap_or(default)
+        };
+        Self {
+            align: get("align")
+                .and_then(|align| match align.as_str() {
+                    "start" | "left" | "top" => Some(Alignment::Start),
+                    "center" => Some(Alignment::Center),
+                    "end" | "right" | "bottom" => Some(Alignment::End),
+                    _ => {
+                        eprintln!("Invalid align value of a popup: {align}");
+                        None
+                    }
+                })
+                .unwrap_or(default.align),
+            offset_x: offset("offset_x", default.offset_x),
+            offset_y: offset("offset_y", default.offset_y),
+            screen_edge: get("screen_edge")
+                .and_then(|adjustments| {
+                    adjustments
+                        .split_whitespace()
+                        .map(|adjustment| match adjustment {
+                            "flip" => Some(FLIP),
+                            "slide" => Some(SLIDE),
+                            "resize" => Some(RESIZE),
+                            "none" => Some(0),
+                            _ => {
+                                eprintln!("Invalid screen_edge value of a popup: {adjustment}");
+                                None
+                            }
+                        })
+                        .try_fold(0, |flags, flag| flag.map(|flag| flags | flag))
+                })
+                .unwrap_or(default.screen_edge),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use configparser::ini::Ini;
+use iced::{widget::tooltip::Position, Background, Border, Color, Padding};
+
+use super::{anchor::BarAnchor, parse::StringExt};
+
+#[derive(Debug, Clone, Copy)]
+pub struct TooltipConfig {
+    pub text_color: Color,
+    pub font_size: f32,
+    pub padding: Padding,
+    pub background: Background,
+    pub border: Border,
+    /// The space between the tooltip and the module
+    pub gap: f32,
+    /// The position relative to the module, `None` places it towards the center of the screen
+    pub position: Option<Position>,
+}
+
+impl Default for TooltipConfig {
+    fn default() -> Self {
+        Self {
+            text_color: Color::WHITE,
+            font_size: 12.,
+            padding: [2, 10].into(),
+            background: Background::Color(Color::BLACK),
+            border: Border {
+                color: Color::WHITE,
+                width: 1.,
+                radius: 5_f32.into(),
+            },
+            gap: 0.,
+            position: None,
+        }
+    }
+}
+
+impl TooltipConfig {
+    pub fn position(&self, anchor: &BarAnchor) -> Position {
+        self.position.unwrap_or(match anchor {
+            BarAnchor::Top => Position::Bottom,
+            BarAnchor::Bottom => Position::Top,
+            BarAnchor::Left => Position::Right,
+            BarAnchor::Right => Position::Left,
+        })
+    }
+}
+
+impl From<&Ini> for TooltipConfig {
+    fn from(ini: &Ini) -> Self {
+        let default = Self::default();
+        let section = "tooltip_style";
+        Self {
+            text_color: ini
+                .get(section, "text_color")
+                .into_color()
+                .unwrap_or(default.text_color),
+            font_size: ini
+                .get(section, "font_size")
+                .into_float()
+                .unwrap_or(default.font_size),
+            padding: ini
+                .get(section, "padding")
+                .into_insets()
+                .map(|i| i.into())
+                .unwrap_or(default.padding),
+            background: ini
+                .get(section, "background")
+                .into_background()
+                .unwrap_or(default.background),
+            border: {
+                let color = ini
+                    .get(section, "border_color")
+                    .into_color()
+                    .unwrap_or(default.border.color);
+                let width = ini
+                    .get(section, "border_width")
+                    .into_float()
+                    .unwrap_or(default.border.width);
+                let radius = ini
+                    .get(section, "border_radius")
+                    .into_insets()
+                    .map(|i| i.into())
+                    .unwrap_or(default.border.radius);
+                Border {
+                    color,
+                    width,
+                    radius,
+                }
+            },
+            gap: ini.get(section, "gap").into_float().unwrap_or(default.gap),
+            position: ini
+                .get(section, "position")
+                .and_then(|position| match position.as_str() {
+                    "auto" => None,
+                    "top" => Some(Position::Top),
+                    "bottom" => Some(Position::Bottom),
+                    "left" => Some(Position::Left),
+                    "right" => Some(Position::Right),
+                    "cursor" => Some(Position::FollowCursor),
+                    _ => {
+                        eprintln!("Invalid tooltip position: {position}");
+                        None
+                    }
+                }),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use configparser::ini::Ini;
+use iced::{
+    widget::{button::Style, container, mouse_area, text},
+    Element, Padding,
+};
+
+use crate::{
+    actions::Visibility,
+    button::button,
+    config::{
+        anchor::BarAnchor,
+        module_config::{LocalModuleConfig, ModuleConfigOverride},
+    },
+    fill::FillExt,
+    list::list,
+    Message, NERD_FONT,
+};
+
+/// Several modules which are shown as one icon, e.g. `group:system = cpu, memory, disk_usage` in
+/// the `[modules]` section. The modules are shown next to the icon while the group is expanded.
+#[derive(Debug)]
+pub struct Group {
+    pub name: String,
+    pub modules: Vec<String>,
+    /// See [Overflow::Hide](crate::section::Overflow::Hide)
+    pub priority: i32,
+    icon: String,
+    open_on: OpenOn,
+    cfg_override: ModuleConfigOverride,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OpenOn {
+    Hover,
+    Click,
+}
+
+impl Group {
+    /// Read the style of the group from its `[group:<name>]` section
+    pub fn read(ini: &Ini, name: &str, modules: Vec<String>) -> Self {
+        let section = format!("group:{name}");
+        Self {
+            name: name.to_string(),
+            modules,
+            priority: ini
+                .get(&section, "priority")
+                .and_then(|priority| priority.parse().ok())
+                .unwrap_or_default(),
+            icon: ini.get(&section, "icon").unwrap_or("".to_string()),
+            open_on: match ini.get(&section, "open_on").as_deref() {
+                None | Some("hover") => OpenOn::Hover,
+                Some("click") => OpenOn::Click,
+                Some(open_on) => {
+                    eprintln!("Invalid open_on value of group {name}: {open_on}");
+                    OpenOn::Hover
+                }
+            },
+            cfg_override: ini
+                .get_map_ref()
+                .get(&section)
+                .map(|map| map.into())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The size the group needs across the bar, given the size of its largest module
+    pub fn thickness(&self, modules: f32, config: &LocalModuleConfig, anchor: &BarAnchor) -> f32 {
+        let cross = |padding: Padding| match anchor.vertical() {
+            true => padding.horizontal(),
+            false => padding.vertical(),
+        };
+        let icon = self.cfg_override.icon_size.unwrap_or(config.icon_size) * 1.3
+            + cross(self.cfg_override.icon_margin.unwrap_or(config.icon_margin));
+        icon.max(modules)
+            + cross(self.cfg_override.padding.unwrap_or_default())
+            + cross(self.cfg_override.margin.unwrap_or_default())
+    }
+
+    /// The icon of the group, followed by its `modules` if it is expanded
+    pub fn view<'a>(
+        &'a self,
+        modules: Vec<Element<'a, Message>>,
+        expanded: bool,
+        config: &LocalModuleConfig,
+        spacing: f32,
+        anchor: &BarAnchor,
+    ) -> Element<'a, Message> {
+        let toggle = Message::ExpandGroup(self.name.clone(), Visibility::Toggle);
+        let open_on = self.open_on;
+        let icon = button(
+            container(
+                text!("{}", self.icon)
+                    .size(self.cfg_override.icon_size.unwrap_or(config.icon_size))
+                    .color(self.cfg_override.icon_color.unwrap_or(config.icon_color))
+                    .font(NERD_FONT)
+                    .fill(anchor),
+            )
+            .fill(anchor)
+            .padding(self.cfg_override.icon_margin.unwrap_or(config.icon_margin)),
+        )
+        .fill(anchor)
+        .on_event_try(move |event, _, _, _, _| {
+            (open_on == OpenOn::Click && event.is_left_click()).then(|| toggle.clone())
+        })
+        .style(|_, _| Style::default());
+        let background = self.cfg_override.background.flatten();
+        let border = self.cfg_override.border.unwrap_or_default();
+        let content = container(
+            list(
+                anchor,
+                [icon.into()]
+                    .into_iter()
+                    .chain(modules.into_iter().filter(|_| expanded)),
+            )
+            .spacing(self.cfg_override.spacing.unwrap_or(spacing)),
+        )
+        .fill(anchor)
+        .padding(self.cfg_override.padding.unwrap_or_default())
+        .style(move |_| container::Style {
+            background,
+            border,
+            ..Default::default()
+        });
+        let content = container(content)
+            .fill(anchor)
+            .padding(self.cfg_override.margin.unwrap_or_default());
+        match self.open_on {
+            OpenOn::Hover => mouse_area(content)
+                .on_enter(Message::ExpandGroup(self.name.clone(), Visibility::Show))
+                .on_exit(Message::ExpandGroup(self.name.clone(), Visibility::Hide))
+                .into(),
+            OpenOn::Click => content.into(),
+        }
+    }
+}
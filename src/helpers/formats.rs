@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use handlebars::Handlebars;
+use iced::{mouse, Event};
+use serde::Serialize;
+
+use crate::{actions::NEXT_FORMAT, button::ButtonEvent, modules::Action};
+
+use super::UnEscapeString;
+
+/// The `format` and the optional `format_alt` of a module, which are rendered using handlebars.
+/// `format:next` or a click switches between them.
+#[derive(Debug)]
+pub struct Formats {
+    /// The name of the `format` template, `format_alt` is registered as `{name}_alt`
+    name: &'static str,
+    has_alt: bool,
+    alt: bool,
+    /// The mouse button which switches the format by default
+    button: mouse::Button,
+}
+
+impl Formats {
+    /// Formats which are switched by a left click
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            has_alt: false,
+            alt: false,
+            button: mouse::Button::Left,
+        }
+    }
+
+    /// Switch the format with another mouse button, for modules which use the left click already,
+    /// e.g. to open their popup
+    pub fn switch_on(mut self, button: mouse::Button) -> Self {
+        self.button = button;
+        self
+    }
+
+    pub fn read_config(
+        &mut self,
+        config: &HashMap<String, Option<String>>,
+        default: &str,
+        templates: &mut Handlebars,
+    ) {
+        let name = self.name;
+        templates
+            .register_template_string(
+                name,
+                config
+                    .get("format")
+                    .unescape()
+                    .unwrap_or(default.to_string()),
+            )
+            .unwrap_or_else(|e| eprintln!("Failed to parse {name} format: {e}"));
+        self.has_alt = match config.get("format_alt").unescape() {
+            Some(alt) => templates
+                .register_template_string(&format!("{name}_alt"), alt)
+                .map_err(|e| eprintln!("Failed to parse {name} format_alt: {e}"))
+                .is_ok(),
+            None => false,
+        };
+        self.alt &= self.has_alt;
+    }
+
+    /// For modules whose `format_alt` isn't a handlebars template, e.g. a strftime format
+    pub fn set_has_alt(&mut self, has_alt: bool) {
+        self.has_alt = has_alt;
+        self.alt &= has_alt;
+    }
+
+    pub fn next(&mut self) {
+        self.alt = self.has_alt && !self.alt;
+    }
+
+    /// Whether `format_alt` is shown
+    pub fn is_alt(&self) -> bool {
+        self.alt
+    }
+
+    pub fn render<T: Serialize>(&self, templates: &Handlebars, ctx: &T) -> String {
+        let template = match self.alt {
+            true => format!("{}_alt", self.name),
+            false => self.name.to_string(),
+        };
+        templates
+            .render(&template, ctx)
+            .map_err(|e| eprintln!("Failed to render {template}: {e}"))
+            .unwrap_or_default()
+    }
+
+    /// Switch the format on a click, if there is a `format_alt`
+    pub fn event(&self, event: &ButtonEvent) -> Option<&dyn Action> {
+        match event.event {
+            Event::Mouse(mouse::Event::ButtonReleased(button))
+                if button == self.button && self.has_alt && event.scroll.is_none() =>
+            {
+                Some(&NEXT_FORMAT)
+            }
+            _ => None,
+        }
+    }
+}
@@ -1,3 +1,13 @@
+mod formats;
+mod templates;
+mod thresholds;
+mod visibility;
+
+pub use formats::Formats;
+pub use templates::{register_helpers, register_script_helpers};
+pub use thresholds::{State, StateStyle, Thresholds};
+pub use visibility::{eval_condition, register_condition, register_show_when, show_when};
+
 pub trait UnEscapeString {
     /// Unescape special characters like '\n' and '\t'
     fn unescape(self) -> Option<String>;
@@ -0,0 +1,186 @@
+use std::{collections::BTreeMap, path::Path};
+
+use configparser::ini::Ini;
+use handlebars::{handlebars_helper, Handlebars, JsonRender, JsonValue};
+
+/// Register the built-in helpers, which can be used in all templates
+pub fn register_helpers(templates: &mut Handlebars) {
+    templates.register_helper("bytes", Box::new(bytes));
+    templates.register_helper("duration", Box::new(duration));
+    templates.register_helper("round", Box::new(round));
+    templates.register_helper("pad", Box::new(pad));
+    templates.register_helper("bar", Box::new(bar));
+    templates.register_helper("sparkline", Box::new(sparkline));
+    templates.register_helper("icon", Box::new(icon));
+}
+
+/// Register the user defined helpers of the `[template_helpers]` section, which maps helper names
+/// to [rhai](https://rhai.rs) scripts. Relative paths are resolved from the config directory.
+pub fn register_script_helpers(ini: &Ini, config_dir: &Path, templates: &mut Handlebars) {
+    let Some(helpers) = ini.get_map_ref().get("template_helpers") else {
+        return;
+    };
+    for (name, path) in helpers {
+        let Some(path) = path else {
+            continue;
+        };
+        let path = config_dir.join(path);
+        templates
+            .register_script_helper_file(name, &path)
+            .unwrap_or_else(|e| {
+                eprintln!(
+                    "Failed to load the template helper {name} from {}: {e}",
+                    path.to_string_lossy()
+                )
+            });
+    }
+}
+
+type Hash<'a> = BTreeMap<&'a str, &'a JsonValue>;
+
+/// Most values are passed to the templates as strings, so accept those as well
+fn number(value: &JsonValue) -> f64 {
+    match value {
+        JsonValue::Number(n) => n.as_f64().unwrap_or_default(),
+        JsonValue::String(s) => s.trim().parse().unwrap_or_default(),
+        JsonValue::Bool(b) => *b as u8 as f64,
+        _ => 0.,
+    }
+}
+
+fn hash_number(hash: &Hash, key: &str, default: f64) -> f64 {
+    hash.get(key).map_or(default, |v| number(v))
+}
+
+fn hash_string(hash: &Hash, key: &str, default: &str) -> String {
+    hash.get(key).map_or(default.to_string(), |v| v.render())
+}
+
+handlebars_helper!(bytes: |value: Json, **hash| {
+    let unit = hash_string(&hash, "unit", "B");
+    let precision = hash_number(&hash, "precision", 1.) as usize;
+    format_bytes(number(value), &unit, precision)
+});
+
+/// Scale the value to the largest unit, in which it is at least 1.
+/// Units like `MB` are scaled by 1000, units like `MiB` by 1024.
+fn format_bytes(mut value: f64, unit: &str, precision: usize) -> String {
+    const BINARY: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+    const DECIMAL: [&str; 6] = ["B", "kB", "MB", "GB", "TB", "PB"];
+    let (units, base) = match DECIMAL[1..].contains(&unit) {
+        true => (DECIMAL, 1000.),
+        false => (BINARY, 1024.),
+    };
+    let mut idx = units.iter().position(|u| *u == unit).unwrap_or_default();
+    while value >= base && idx < units.len() - 1 {
+        value /= base;
+        idx += 1;
+    }
+    format!("{value:.precision$} {}", units[idx])
+}
+
+handlebars_helper!(duration: |value: Json, **hash| {
+    let secs = number(value)
+        * match hash_string(&hash, "unit", "s").as_str() {
+            "ms" => 0.001,
+            "min" => 60.,
+            "h" => 3600.,
+            _ => 1.,
+        };
+    format_duration(secs.max(0.).round() as u64)
+});
+
+/// Show the two most significant units, e.g. `2h 5min`
+fn format_duration(secs: u64) -> String {
+    let parts = [
+        (secs / 86400, "d"),
+        (secs / 3600 % 24, "h"),
+        (secs / 60 % 60, "min"),
+        (secs % 60, "s"),
+    ]
+    .into_iter()
+    .skip_while(|(n, _)| *n == 0)
+    .take(2)
+    .filter(|(n, _)| *n != 0)
+    .map(|(n, unit)| format!("{n}{unit}"))
+    .collect::<Vec<String>>();
+    match parts.is_empty() {
+        true => "0s".to_string(),
+        false => parts.join(" "),
+    }
+}
+
+handlebars_helper!(round: |value: Json, **hash| {
+    let precision = hash_number(&hash, "precision", 0.) as usize;
+    format!("{:.precision$}", number(value))
+});
+
+handlebars_helper!(pad: |value: Json, width: u64, **hash| {
+    let value = value.render();
+    let fill = hash_string(&hash, "fill", " ");
+    let padding = fill.repeat((width as usize).saturating_sub(value.chars().count()));
+    match hash_string(&hash, "align", "right").as_str() {
+        "left" => value + &padding,
+        _ => padding + &value,
+    }
+});
+
+handlebars_helper!(bar: |value: Json, **hash| {
+    let width = hash_number(&hash, "width", 10.) as usize;
+    let fraction = (number(value) / hash_number(&hash, "max", 100.)).clamp(0., 1.);
+    let empty = hash_string(&hash, "empty", " ");
+    match hash.get("full") {
+        Some(full) => {
+            let full_cells = (fraction * width as f64).round() as usize;
+            full.render().repeat(full_cells) + &empty.repeat(width - full_cells)
+        }
+        // Use eighth blocks for a smooth bar
+        None => {
+            const PARTIAL: [&str; 8] = ["", "▏", "▎", "▍", "▌", "▋", "▊", "▉"];
+            let eighths = (fraction * width as f64 * 8.).round() as usize;
+            let partial = PARTIAL[eighths % 8];
+            let used_cells = eighths / 8 + !partial.is_empty() as usize;
+            "█".repeat(eighths / 8) + partial + &empty.repeat(width - used_cells)
+        }
+    }
+});
+
+handlebars_helper!(sparkline: |values: Json, **hash| {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    // Either an array or a list of numbers separated by commas or whitespace
+    let values: Vec<f64> = match values {
+        JsonValue::Array(values) => values.iter().map(number).collect(),
+        values => values
+            .render()
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.parse().unwrap_or_default())
+            .collect(),
+    };
+    let max = hash_number(
+        &hash,
+        "max",
+        values.iter().copied().fold(0., f64::max),
+    );
+    values
+        .iter()
+        .map(|v| match max > 0. {
+            true => BLOCKS[((v / max).clamp(0., 1.) * 7.).round() as usize],
+            false => BLOCKS[0],
+        })
+        .collect::<String>()
+});
+
+handlebars_helper!(icon: |value: Json, thresholds: str| {
+    // e.g. "0:󰂎 25:󱊡 60:󱊢 80:󱊣", the icon of the highest threshold below the value is used
+    let value = number(value);
+    thresholds
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(|entry| entry.split_once(':'))
+        .filter_map(|(threshold, icon)| Some((threshold.parse::<f64>().ok()?, icon)))
+        .filter(|(threshold, _)| value >= *threshold)
+        .max_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, icon)| icon)
+        .unwrap_or_default()
+        .to_string()
+});
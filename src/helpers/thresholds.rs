@@ -0,0 +1,193 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+};
+
+use iced::{Background, Border, Color};
+
+use crate::config::{module_config::ModuleConfigOverride, parse::StringExt};
+
+/// The state of a module, depending on the `warning` and `critical` thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Normal,
+    Warning,
+    Critical,
+}
+
+impl Display for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            State::Normal => "normal",
+            State::Warning => "warning",
+            State::Critical => "critical",
+        })
+    }
+}
+
+/// The style of a module in its current state, which takes precedence over its `cfg_override`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StateStyle {
+    pub text_color: Option<Color>,
+    pub icon_color: Option<Color>,
+    pub background: Option<Option<Background>>,
+    pub border: Option<Border>,
+}
+
+impl From<&ModuleConfigOverride> for StateStyle {
+    fn from(cfg: &ModuleConfigOverride) -> Self {
+        Self {
+            text_color: cfg.text_color,
+            icon_color: cfg.icon_color,
+            background: cfg.background,
+            border: cfg.border,
+        }
+    }
+}
+
+/// The `warning` and `critical` thresholds of a numeric module, with a style for each state.
+/// The styles are read from options prefixed with the state, e.g. `warning_text_color`.
+#[derive(Debug)]
+pub struct Thresholds {
+    /// The context value which is compared to the thresholds
+    value: String,
+    default_value: &'static str,
+    /// Whether lower values are worse, e.g. for the battery capacity
+    descending: bool,
+    default_descending: bool,
+    warning: Option<f32>,
+    critical: Option<f32>,
+    warning_style: StateStyle,
+    critical_style: StateStyle,
+    /// Blend the colors of the warning and critical style between both thresholds
+    interpolate: bool,
+}
+
+impl Thresholds {
+    /// Thresholds of a value, which is worse the higher it gets
+    pub fn new(value: &'static str) -> Self {
+        Self {
+            value: value.to_string(),
+            default_value: value,
+            descending: false,
+            default_descending: false,
+            warning: None,
+            critical: None,
+            warning_style: StateStyle::default(),
+            critical_style: StateStyle::default(),
+            interpolate: false,
+        }
+    }
+
+    /// Thresholds of a value, which is worse the lower it gets
+    pub fn descending(value: &'static str) -> Self {
+        Self {
+            descending: true,
+            default_descending: true,
+            ..Self::new(value)
+        }
+    }
+
+    pub fn read_config(&mut self, config: &HashMap<String, Option<String>>) {
+        self.value = config
+            .get("threshold_value")
+            .cloned()
+            .flatten()
+            .unwrap_or(self.default_value.to_string());
+        self.warning = config.get("warning").and_then(|v| v.into_float());
+        self.critical = config.get("critical").and_then(|v| v.into_float());
+        // If both are set, their order decides the direction
+        self.descending = match (self.warning, self.critical) {
+            (Some(warning), Some(critical)) => critical < warning,
+            _ => self.default_descending,
+        };
+        let style = |state: &str| -> StateStyle {
+            let prefix = format!("{state}_");
+            let options: HashMap<String, Option<String>> = config
+                .iter()
+                .filter_map(|(key, value)| {
+                    key.strip_prefix(&prefix)
+                        .map(|key| (key.to_string(), value.clone()))
+                })
+                .collect();
+            (&ModuleConfigOverride::from(&options)).into()
+        };
+        self.warning_style = style("warning");
+        self.critical_style = style("critical");
+        self.interpolate = config
+            .get("interpolate_colors")
+            .and_then(|v| v.into_bool())
+            .unwrap_or(false);
+    }
+
+    fn value(&self, ctx: &BTreeMap<&'static str, String>) -> Option<f32> {
+        ctx.get(self.value.as_str())?.trim().parse().ok()
+    }
+
+    fn reached(&self, value: f32, threshold: Option<f32>) -> bool {
+        threshold.is_some_and(|threshold| match self.descending {
+            true => value <= threshold,
+            false => value >= threshold,
+        })
+    }
+
+    /// The state of a module, `ctx` is its [context](crate::modules::Module::context)
+    pub fn state(&self, ctx: &BTreeMap<&'static str, String>) -> State {
+        match self.value(ctx) {
+            Some(value) if self.reached(value, self.critical) => State::Critical,
+            Some(value) if self.reached(value, self.warning) => State::Warning,
+            _ => State::Normal,
+        }
+    }
+
+    /// The style of a module in its current state
+    pub fn style(&self, ctx: &BTreeMap<&'static str, String>) -> StateStyle {
+        match self.state(ctx) {
+            State::Normal => StateStyle::default(),
+            State::Critical => self.critical_style,
+            State::Warning => match (self.interpolate, self.warning, self.critical) {
+                (true, Some(warning), Some(critical)) => {
+                    let value = self.value(ctx).unwrap_or(warning);
+                    self.blend(((value - warning) / (critical - warning)).clamp(0., 1.))
+                }
+                _ => self.warning_style,
+            },
+        }
+    }
+
+    /// Blend the warning style into the critical style, `t` is between 0 and 1
+    fn blend(&self, t: f32) -> StateStyle {
+        let (from, to) = (self.warning_style, self.critical_style);
+        let color = |from: Option<Color>, to: Option<Color>| match (from, to) {
+            (Some(from), Some(to)) => Some(mix(from, to, t)),
+            _ => from,
+        };
+        StateStyle {
+            text_color: color(from.text_color, to.text_color),
+            icon_color: color(from.icon_color, to.icon_color),
+            background: match (from.background, to.background) {
+                (Some(Some(Background::Color(from))), Some(Some(Background::Color(to)))) => {
+                    Some(Some(Background::Color(mix(from, to, t))))
+                }
+                (background, _) => background,
+            },
+            border: match (from.border, to.border) {
+                (Some(from), Some(to)) => Some(Border {
+                    color: mix(from.color, to.color, t),
+                    ..from
+                }),
+                (border, _) => border,
+            },
+        }
+    }
+}
+
+fn mix(from: Color, to: Color, t: f32) -> Color {
+    let lerp = |a: f32, b: f32| a + (b - a) * t;
+    Color {
+        r: lerp(from.r, to.r),
+        g: lerp(from.g, to.g),
+        b: lerp(from.b, to.b),
+        a: lerp(from.a, to.a),
+    }
+}
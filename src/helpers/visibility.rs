@@ -0,0 +1,61 @@
+use handlebars::{Handlebars, JsonValue, RenderError, TemplateError};
+use serde::Serialize;
+
+use crate::{config::Config, modules::Module, variables::module_values};
+
+fn template_name(module: &str) -> String {
+    format!("show_when:{module}")
+}
+
+/// Register a condition, which is a handlebars expression like `gt used_perc 80` that is
+/// evaluated by `#if`.
+pub fn register_condition(
+    name: &str,
+    condition: &str,
+    templates: &mut Handlebars,
+) -> Result<(), TemplateError> {
+    let condition = condition.trim();
+    // A helper call has to be wrapped in a subexpression, a plain value doesn't
+    let condition = match condition.contains(char::is_whitespace) {
+        true => format!("({condition})"),
+        false => condition.to_string(),
+    };
+    templates.register_template_string(name, format!("{{{{#if {condition}}}}}true{{{{/if}}}}"))
+}
+
+/// Evaluate a condition registered by [register_condition]
+pub fn eval_condition<T: Serialize>(
+    name: &str,
+    ctx: &T,
+    templates: &Handlebars,
+) -> Result<bool, RenderError> {
+    templates.render(name, ctx).map(|result| result == "true")
+}
+
+/// Register the `show_when` rule of a module. Returns whether the rule is valid.
+pub fn register_show_when(module: &str, rule: &str, templates: &mut Handlebars) -> bool {
+    register_condition(&template_name(module), rule, templates)
+        .map_err(|e| eprintln!("Failed to parse the show_when rule of {module}: {e}"))
+        .is_ok()
+}
+
+/// Whether a module should be shown according to its `show_when` rule. `state` contains the
+/// values of all modules, see [module_values].
+pub fn show_when(
+    module: &dyn Module,
+    state: &JsonValue,
+    config: &Config,
+    templates: &Handlebars,
+) -> bool {
+    let name = module.name();
+    if !config.show_when.contains(&name) {
+        return true;
+    }
+    eval_condition(
+        &template_name(&name),
+        &module_values(state, module),
+        templates,
+    )
+    .map_err(|e| eprintln!("Failed to evaluate the show_when rule of {name}: {e}"))
+    .unwrap_or(false)
+}
@@ -0,0 +1,188 @@
+use std::{
+    any::TypeId,
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+
+use handlebars::Handlebars;
+use iced::{Rectangle, Task};
+use tokio::time::sleep;
+
+use crate::{
+    config::Config,
+    helpers::{eval_condition, register_condition},
+    modules::{empty::EmptyModule, Action, ActionContext},
+    registry::Registry,
+    variables::Variables,
+    Message,
+};
+
+/// The `[hooks]` section: actions which are run when a variable changes (`change <variable>`)
+/// or when a condition becomes true (`when <condition>`).
+#[derive(Debug)]
+pub struct Hooks {
+    hooks: Vec<Hook>,
+    /// How long a new value has to persist before the hooks fire
+    debounce: Duration,
+}
+
+impl Default for Hooks {
+    fn default() -> Self {
+        Self {
+            hooks: vec![],
+            debounce: Duration::from_millis(500),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Hook {
+    trigger: Trigger,
+    action: Box<dyn Action>,
+    /// Overrides the debounce time of all hooks
+    debounce: Option<Duration>,
+}
+
+#[derive(Debug)]
+enum Trigger {
+    /// The name of a variable, e.g. `media.title`
+    Change(String),
+    /// The name of the registered condition template
+    When(String),
+}
+
+/// The last value seen by a hook, reset when the config is reloaded
+#[derive(Debug, Default)]
+pub struct HookState {
+    /// The last stable value, `None` until the first value is stable
+    last: Option<String>,
+    /// A new value and since when it is observed
+    pending: Option<(String, Instant)>,
+}
+
+impl Hooks {
+    pub fn read(
+        section: Option<&HashMap<String, Option<String>>>,
+        templates: &mut Handlebars,
+    ) -> Self {
+        let mut hooks = Self::default();
+        for (key, value) in section.into_iter().flatten() {
+            let Some(value) = value else {
+                continue;
+            };
+            if key == "debounce" {
+                match value.parse() {
+                    Ok(ms) => hooks.debounce = Duration::from_millis(ms),
+                    Err(e) => eprintln!("Invalid hook debounce {value}: {e}"),
+                }
+                continue;
+            }
+            let trigger = match key.split_once(' ') {
+                Some(("change", variable)) => Trigger::Change(variable.trim().to_string()),
+                Some(("when", condition)) => {
+                    let name = format!("hook:{}", hooks.hooks.len());
+                    if let Err(e) = register_condition(&name, condition, templates) {
+                        eprintln!("Failed to parse the condition of the hook `{key}`: {e}");
+                        continue;
+                    }
+                    Trigger::When(name)
+                }
+                _ => {
+                    eprintln!("Invalid hook: {key}\nHooks start with `change` or `when`");
+                    continue;
+                }
+            };
+            hooks.hooks.push(Hook {
+                trigger,
+                action: value.into(),
+                debounce: None,
+            });
+        }
+        hooks
+    }
+
+    /// Run an action as soon as a variable changes, without waiting for the debounce time
+    pub fn watch(&mut self, variable: String, action: Box<dyn Action>) {
+        self.hooks.push(Hook {
+            trigger: Trigger::Change(variable),
+            action,
+            debounce: Some(Duration::ZERO),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Check every hook for new values, `variables` have to be up to date. Returns the actions
+    /// of the hooks which fired, and a check after the debounce time for new values.
+    pub fn run(
+        &self,
+        states: &mut Vec<HookState>,
+        variables: &Variables,
+        registry: &Registry,
+        config: &Config,
+        templates: &Handlebars,
+    ) -> Task<Message> {
+        states.resize_with(self.hooks.len(), HookState::default);
+        let state_json = self
+            .hooks
+            .iter()
+            .any(|hook| matches!(hook.trigger, Trigger::When(_)))
+            .then(|| variables.json());
+        let now = Instant::now();
+        let mut tasks = vec![];
+        let mut check_later = false;
+        for (hook, state) in self.hooks.iter().zip(states.iter_mut()) {
+            let value = match &hook.trigger {
+                Trigger::Change(variable) => variables.get(variable).unwrap_or_default(),
+                Trigger::When(name) => eval_condition(name, &state_json, templates)
+                    .map_err(|e| eprintln!("Failed to evaluate the condition of a hook: {e}"))
+                    .unwrap_or(false)
+                    .to_string(),
+            };
+            let debounce = hook.debounce.unwrap_or(self.debounce);
+            if state.last.as_ref() == Some(&value) {
+                state.pending = None;
+                continue;
+            }
+            match &state.pending {
+                Some((pending, since)) if *pending == value => {
+                    if now.duration_since(*since) < debounce {
+                        continue;
+                    }
+                }
+                _ => {
+                    state.pending = Some((value.clone(), now));
+                    if !debounce.is_zero() {
+                        check_later = true;
+                        continue;
+                    }
+                }
+            }
+            state.pending = None;
+            // The first value is only remembered, modules may not have loaded their data yet
+            let Some(previous) = state.last.replace(value.clone()) else {
+                continue;
+            };
+            if matches!(hook.trigger, Trigger::When(_)) && value != "true" {
+                continue;
+            }
+            let module = match &hook.trigger {
+                Trigger::Change(variable) => variable
+                    .rsplit_once('.')
+                    .and_then(|(module, _)| registry.resolve(module, Some(config))),
+                Trigger::When(_) => None,
+            };
+            tasks.push(Task::done(hook.action.as_message(&ActionContext {
+                module: module.unwrap_or(TypeId::of::<EmptyModule>()),
+                bounds: Rectangle::default(),
+                vars: BTreeMap::from([("value", value), ("previous", previous)]),
+            })));
+        }
+        if check_later {
+            tasks.push(Task::perform(sleep(self.debounce), |_| Message::RunHooks));
+        }
+        Task::batch(tasks)
+    }
+}
@@ -1,23 +1,10 @@
 use iced::{
-    widget::{column, row, Column, Container, Row},
-    Alignment, Element, Padding, Pixels,
+    widget::{column, row, Column, Row},
+    Element, Padding, Pixels,
 };
 
 use crate::config::anchor::BarAnchor;
 
-pub trait DynamicAlign {
-    fn align(self, anchor: &BarAnchor, alignment: Alignment) -> Self;
-}
-
-impl<Message> DynamicAlign for Container<'_, Message> {
-    fn align(self, anchor: &BarAnchor, alignment: Alignment) -> Self {
-        match anchor.vertical() {
-            true => self.align_y(alignment),
-            false => self.align_x(alignment),
-        }
-    }
-}
-
 pub enum List<'a, Message, Theme, Renderer> {
     Row(Row<'a, Message, Theme, Renderer>),
     Column(Column<'a, Message, Theme, Renderer>),
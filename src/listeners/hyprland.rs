@@ -1,6 +1,9 @@
 use bar_rs_derive::Builder;
 use hyprland::{data::Client, event_listener::AsyncEventListener, shared::HyprDataActiveOptional};
-use iced::{futures::SinkExt, stream, Subscription};
+use iced::{
+    futures::{channel::mpsc::Sender, SinkExt},
+    stream, Subscription,
+};
 
 use crate::{
     config::ConfigEntry,
@@ -16,6 +19,34 @@ use super::Listener;
 #[derive(Debug, Builder)]
 pub struct HyprListener;
 
+async fn update_workspaces(sender: &mut Sender<Message>, active: Option<i32>) {
+    let workspaces = get_workspaces(active).await;
+    let fullscreen = workspaces
+        .1
+        .get(workspaces.0)
+        .is_some_and(|(_, _, fullscreen, _)| *fullscreen);
+    sender
+        .send(Message::Batch(vec![
+            Message::update(move |reg| {
+                let ws = reg.get_module_mut::<HyprWorkspaceMod>();
+                ws.active = workspaces.0;
+                ws.open = workspaces.1;
+                ws.highlight.set(ws.active as f32);
+            }),
+            Message::Fullscreen(fullscreen),
+        ]))
+        .await
+        .unwrap_or_else(|err| {
+            eprintln!("Trying to send workspaces failed with err: {err}");
+        });
+}
+
+async fn update_active_window(sender: &mut Sender<Message>) {
+    if let Ok(window) = Client::get_active_async().await {
+        update_window(sender, window).await;
+    }
+}
+
 impl Listener for HyprListener {
     fn config(&self) -> Vec<ConfigEntry> {
         vec![]
@@ -23,28 +54,24 @@ impl Listener for HyprListener {
     fn subscription(&self) -> Subscription<Message> {
         Subscription::run(|| {
             stream::channel(1, |mut sender| async move {
-                let workspaces = get_workspaces(None).await;
-                sender
-                    .send(Message::update(move |reg| {
-                        let ws = reg.get_module_mut::<HyprWorkspaceMod>();
-                        ws.active = workspaces.0;
-                        ws.open = workspaces.1;
-                    }))
-                    .await
-                    .unwrap_or_else(|err| {
-                        eprintln!("Trying to send workspaces failed with err: {err}");
-                    });
-                if let Ok(window) = Client::get_active_async().await {
-                    update_window(&mut sender, window.map(|w| w.title)).await;
-                }
+                update_workspaces(&mut sender, None).await;
+                update_active_window(&mut sender).await;
 
                 let mut listener = AsyncEventListener::new();
 
                 let senderx = sender.clone();
-                listener.add_active_window_changed_handler(move |data| {
+                listener.add_active_window_changed_handler(move |_| {
                     let mut sender = senderx.clone();
                     Box::pin(async move {
-                        update_window(&mut sender, data.map(|window| window.title)).await;
+                        update_active_window(&mut sender).await;
+                    })
+                });
+
+                let senderx = sender.clone();
+                listener.add_float_state_changed_handler(move |_| {
+                    let mut sender = senderx.clone();
+                    Box::pin(async move {
+                        update_active_window(&mut sender).await;
                     })
                 });
 
@@ -52,17 +79,40 @@ impl Listener for HyprListener {
                 listener.add_workspace_changed_handler(move |data| {
                     let mut sender = senderx.clone();
                     Box::pin(async move {
-                        let workspaces = get_workspaces(Some(data.id)).await;
-                        sender
-                            .send(Message::update(move |reg| {
-                                let ws = reg.get_module_mut::<HyprWorkspaceMod>();
-                                ws.active = workspaces.0;
-                                ws.open = workspaces.1;
-                            }))
-                            .await
-                            .unwrap_or_else(|err| {
-                                eprintln!("Trying to send workspaces failed with err: {err}");
-                            });
+                        update_workspaces(&mut sender, Some(data.id)).await;
+                    })
+                });
+
+                // The fullscreen state and the window count are part of the workspaces
+                let senderx = sender.clone();
+                listener.add_fullscreen_state_changed_handler(move |_| {
+                    let mut sender = senderx.clone();
+                    Box::pin(async move {
+                        update_workspaces(&mut sender, None).await;
+                    })
+                });
+
+                let senderx = sender.clone();
+                listener.add_window_opened_handler(move |_| {
+                    let mut sender = senderx.clone();
+                    Box::pin(async move {
+                        update_workspaces(&mut sender, None).await;
+                    })
+                });
+
+                let senderx = sender.clone();
+                listener.add_window_closed_handler(move |_| {
+                    let mut sender = senderx.clone();
+                    Box::pin(async move {
+                        update_workspaces(&mut sender, None).await;
+                    })
+                });
+
+                let senderx = sender.clone();
+                listener.add_window_moved_handler(move |_| {
+                    let mut sender = senderx.clone();
+                    Box::pin(async move {
+                        update_workspaces(&mut sender, None).await;
                     })
                 });
 
@@ -0,0 +1,111 @@
+use std::{
+    env,
+    io::{self, Read, Write},
+    os::unix::net::UnixStream as StdUnixStream,
+    path::PathBuf,
+};
+
+use bar_rs_derive::Builder;
+use iced::{
+    futures::{channel::mpsc::Sender, SinkExt},
+    stream, Subscription,
+};
+use tokio::{
+    fs,
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    sync::mpsc,
+};
+
+use crate::{config::ConfigEntry, Message};
+
+use super::Listener;
+
+/// Listens on a unix socket for commands like `vars`, which can be sent using
+/// `bar-rs msg <command>`
+#[derive(Debug, Builder)]
+pub struct IpcListener;
+
+impl Listener for IpcListener {
+    fn config(&self) -> Vec<ConfigEntry> {
+        vec![ConfigEntry::new("general", "ipc", true)]
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        Subscription::run(|| {
+            stream::channel(1, |mut sender| async move {
+                let path = socket_path();
+                if UnixStream::connect(&path).await.is_ok() {
+                    eprintln!(
+                        "Another instance of bar-rs is listening on {}, not starting the ipc",
+                        path.to_string_lossy()
+                    );
+                    return;
+                }
+                // Remove the socket of a previous instance
+                let _ = fs::remove_file(&path).await;
+                let Ok(listener) = UnixListener::bind(&path).map_err(|e| {
+                    eprintln!(
+                        "Failed to bind the ipc socket at {}: {e}",
+                        path.to_string_lossy()
+                    )
+                }) else {
+                    return;
+                };
+                loop {
+                    let Ok((stream, _)) = listener
+                        .accept()
+                        .await
+                        .map_err(|e| eprintln!("Failed to accept an ipc connection: {e}"))
+                    else {
+                        continue;
+                    };
+                    // A slow client shouldn't block the others
+                    tokio::spawn(handle_client(stream, sender.clone()));
+                }
+            })
+        })
+    }
+}
+
+async fn handle_client(stream: UnixStream, mut sender: Sender<Message>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut command = String::new();
+    if BufReader::new(reader)
+        .read_line(&mut command)
+        .await
+        .is_err()
+    {
+        return;
+    }
+    let (sx, mut rx) = mpsc::channel(1);
+    sender
+        .send(Message::Ipc(command.trim().to_string(), sx))
+        .await
+        .unwrap_or_else(|err| {
+            eprintln!("Trying to send an ipc command failed with err: {err}");
+        });
+    if let Some(reply) = rx.recv().await {
+        writer
+            .write_all(reply.as_bytes())
+            .await
+            .unwrap_or_else(|e| eprintln!("Failed to answer an ipc command: {e}"));
+    }
+}
+
+fn socket_path() -> PathBuf {
+    env::var("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| env::temp_dir())
+        .join("bar-rs.sock")
+}
+
+/// Send a command to the running instance of bar-rs and return its reply
+pub fn send_command(command: &str) -> io::Result<String> {
+    let mut stream = StdUnixStream::connect(socket_path())?;
+    stream.write_all(format!("{command}\n").as_bytes())?;
+    stream.shutdown(std::net::Shutdown::Write)?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply)?;
+    Ok(reply)
+}
@@ -3,6 +3,7 @@ use std::{any::Any, fmt::Debug};
 use downcast_rs::{impl_downcast, Downcast};
 use hyprland::HyprListener;
 use iced::Subscription;
+use ipc::IpcListener;
 use niri::NiriListener;
 use reload::ReloadListener;
 use wayfire::WayfireListener;
@@ -10,6 +11,7 @@ use wayfire::WayfireListener;
 use crate::{config::ConfigEntry, registry::Registry, Message};
 
 pub mod hyprland;
+pub mod ipc;
 pub mod niri;
 mod reload;
 pub mod wayfire;
@@ -27,4 +29,5 @@ pub fn register_listeners(registry: &mut Registry) {
     registry.register_listener::<WayfireListener>();
     registry.register_listener::<NiriListener>();
     registry.register_listener::<ReloadListener>();
+    registry.register_listener::<IpcListener>();
 }
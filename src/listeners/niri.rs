@@ -1,8 +1,8 @@
-use std::{collections::HashMap, env, sync::Arc};
+use std::{collections::HashMap, env, error::Error, sync::Arc};
 
 use bar_rs_derive::Builder;
 use iced::{futures::SinkExt, stream, Subscription};
-use niri_ipc::{socket::SOCKET_PATH_ENV, Event, Request};
+use niri_ipc::{socket::SOCKET_PATH_ENV, Event, Reply, Request, Response};
 use tokio::{
     io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
     net::UnixStream,
@@ -38,6 +38,10 @@ impl Listener for NiriListener {
                 let mut receiver = rx.recv().await.unwrap();
                 drop(rx);
                 let socket_path = env::var(SOCKET_PATH_ENV).expect("No niri socket was found!");
+                let mut state = FullscreenState {
+                    outputs: get_outputs(&socket_path).await,
+                    ..Default::default()
+                };
                 let mut socket = UnixStream::connect(&socket_path).await.unwrap();
                 let mut buf = serde_json::to_string(&Request::EventStream).unwrap();
                 socket.write_all(buf.as_bytes()).await.unwrap();
@@ -55,6 +59,14 @@ impl Listener for NiriListener {
                     tokio::select! {
                         Ok(_) = reader.read_line(&mut buf) => {
                             let reply = serde_json::from_str::<Event>(&buf);
+                            if let Ok(event) = &reply {
+                                // Outputs come and go with their workspaces
+                                if matches!(event, Event::WorkspacesChanged { .. }) {
+                                    state.outputs = get_outputs(&socket_path).await;
+                                }
+                                state.handle(event);
+                            }
+                            let fullscreen = state.fullscreen();
                             type F = Box<dyn FnOnce(&mut Registry) + Send + Sync>;
                             let msg: Option<F> = match reply {
                                 Ok(event) => match event {
@@ -122,20 +134,36 @@ impl Listener for NiriListener {
                                 }
                                 };
                                 if let Some(msg) = msg {
+                                    let msg: F = Box::new(move |reg| {
+                                        msg(reg);
+                                        NiriWindowMod::update_state(reg);
+                                        let window_mod = reg.get_module_mut::<NiriWindowMod>();
+                                        window_mod.fullscreen = fullscreen;
+                                        reg.get_module_mut::<NiriWorkspaceMod>().update_highlight();
+                                    });
                                     sender
                                         .send(Message::Update(Arc::new(UpdateFn(msg))))
                                         .await
                                         .unwrap();
                                 }
+                                if fullscreen != state.reported {
+                                    state.reported = fullscreen;
+                                    sender.send(Message::Fullscreen(fullscreen)).await.unwrap();
+                                }
                                 buf.clear();
                             }
                         Ok(action) = receiver.recv() => {
-                            if let Some(id) = action.downcast_ref::<u64>() {
-                                let mut socket = UnixStream::connect(&socket_path).await.unwrap();
-                                let buf = serde_json::to_string(&Request::Action(niri_ipc::Action::FocusWorkspace { reference: niri_ipc::WorkspaceReferenceArg::Id(*id) })).unwrap();
-                                socket.write_all(buf.as_bytes()).await.unwrap();
-                                socket.shutdown().await.unwrap();
-                            }
+                            let action = match action.downcast_ref::<u64>() {
+                                Some(id) => niri_ipc::Action::FocusWorkspace { reference: niri_ipc::WorkspaceReferenceArg::Id(*id) },
+                                None => match action.downcast_ref::<niri_ipc::Action>() {
+                                    Some(action) => action.clone(),
+                                    None => continue,
+                                },
+                            };
+                            let mut socket = UnixStream::connect(&socket_path).await.unwrap();
+                            let buf = serde_json::to_string(&Request::Action(action)).unwrap();
+                            socket.write_all(buf.as_bytes()).await.unwrap();
+                            socket.shutdown().await.unwrap();
                         }
                     }
                 }
@@ -143,3 +171,90 @@ impl Listener for NiriListener {
         })
     }
 }
+
+/// What is needed to tell whether the focused window is fullscreen. Niri doesn't report this, so
+/// the size of the window is compared to the size of its output instead.
+#[derive(Debug, Default)]
+struct FullscreenState {
+    focused: Option<u64>,
+    /// The workspace and the size of each window by its id
+    windows: HashMap<u64, (Option<u64>, (i32, i32))>,
+    /// The output of each workspace by its id
+    workspaces: HashMap<u64, String>,
+    /// The logical size of each output by its name
+    outputs: HashMap<String, (u32, u32)>,
+    /// The last state which was sent to the bar
+    reported: bool,
+}
+
+impl FullscreenState {
+    fn handle(&mut self, event: &Event) {
+        match event {
+            Event::WorkspacesChanged { workspaces } => {
+                self.workspaces = workspaces
+                    .iter()
+                    .filter_map(|ws| Some((ws.id, ws.output.clone()?)))
+                    .collect();
+            }
+            Event::WindowsChanged { windows } => {
+                self.focused = windows.iter().find(|w| w.is_focused).map(|w| w.id);
+                self.windows = windows
+                    .iter()
+                    .map(|w| (w.id, (w.workspace_id, w.layout.window_size)))
+                    .collect();
+            }
+            Event::WindowFocusChanged { id } => self.focused = *id,
+            Event::WindowOpenedOrChanged { window } => {
+                if window.is_focused {
+                    self.focused = Some(window.id);
+                }
+                self.windows
+                    .insert(window.id, (window.workspace_id, window.layout.window_size));
+            }
+            Event::WindowClosed { id } => {
+                self.windows.remove(id);
+            }
+            _ => {}
+        }
+    }
+
+    fn fullscreen(&self) -> bool {
+        self.focused
+            .and_then(|id| self.windows.get(&id))
+            .and_then(|(workspace, (window_width, window_height))| {
+                let (width, height) = self.outputs.get(self.workspaces.get(&(*workspace)?)?)?;
+                Some(*window_width as u32 >= *width && *window_height as u32 >= *height)
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// The logical size of each output by its name
+async fn get_outputs(socket_path: &str) -> HashMap<String, (u32, u32)> {
+    request_outputs(socket_path).await.unwrap_or_else(|e| {
+        eprintln!("Failed to get the outputs from niri: {e}");
+        HashMap::new()
+    })
+}
+
+async fn request_outputs(socket_path: &str) -> Result<HashMap<String, (u32, u32)>, Box<dyn Error>> {
+    let mut socket = UnixStream::connect(socket_path).await?;
+    socket
+        .write_all(serde_json::to_string(&Request::Outputs)?.as_bytes())
+        .await?;
+    socket.shutdown().await?;
+    let mut buf = String::new();
+    BufReader::new(socket).read_line(&mut buf).await?;
+    match serde_json::from_str::<Reply>(&buf)? {
+        Ok(Response::Outputs(outputs)) => Ok(outputs
+            .into_iter()
+            .filter_map(|(name, output)| {
+                output
+                    .logical
+                    .map(|logical| (name, (logical.width, logical.height)))
+            })
+            .collect()),
+        Ok(response) => Err(format!("Unexpected response: {response:?}").into()),
+        Err(e) => Err(e.into()),
+    }
+}
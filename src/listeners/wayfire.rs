@@ -34,6 +34,41 @@ async fn send_first_values(
     Ok(())
 }
 
+/// The state of the view of an event
+#[derive(Debug, Default, Clone, Copy)]
+struct ViewState {
+    id: i64,
+    /// Whether there is a view at all, it is null if no view is focused
+    focused: bool,
+    fullscreen: bool,
+    floating: bool,
+}
+
+impl ViewState {
+    fn from_event(msg: &serde_json::Map<String, Value>) -> Option<Self> {
+        let view = msg.get("view")?.as_object()?;
+        Some(Self {
+            id: view.get("id")?.as_i64()?,
+            focused: true,
+            fullscreen: view.get("fullscreen")?.as_bool()?,
+            // A view which isn't attached to any edge is floating
+            floating: view.get("tiled-edges").and_then(|e| e.as_u64()) == Some(0),
+        })
+    }
+
+    fn update(self) -> Message {
+        Message::Batch(vec![
+            Message::update(move |reg| {
+                let window_mod = reg.get_module_mut::<WayfireWindowMod>();
+                window_mod.focused = self.focused;
+                window_mod.fullscreen = self.fullscreen;
+                window_mod.floating = self.floating;
+            }),
+            Message::Fullscreen(self.focused && self.fullscreen),
+        ])
+    }
+}
+
 impl Listener for WayfireListener {
     fn subscription(&self) -> iced::Subscription<Message> {
         Subscription::run(|| {
@@ -55,11 +90,14 @@ impl Listener for WayfireListener {
                         "view-focused".to_string(),
                         "view-title-changed".to_string(),
                         "view-unmapped".to_string(),
+                        "view-fullscreen".to_string(),
+                        "view-tiled".to_string(),
                     ]))
                     .await
                     .expect("Failed to watch wayfire socket (but we're connected already)!");
 
                 let mut active_window = None;
+                let mut focused_view = None;
 
                 while let Ok(Value::Object(msg)) = socket.read_message().await {
                     match msg.get("event") {
@@ -89,9 +127,25 @@ impl Listener for WayfireListener {
                             }
                         }
 
+                        Some(Value::String(val))
+                            if val == "view-fullscreen" || val == "view-tiled" =>
+                        {
+                            let Some(view) = ViewState::from_event(&msg) else {
+                                continue;
+                            };
+                            if Some(view.id) == focused_view {
+                                sender.send(view.update()).await.unwrap();
+                            }
+                        }
+
                         Some(Value::String(val))
                             if val == "view-focused" || val == "view-title-changed" =>
                         {
+                            if val == "view-focused" {
+                                let view = ViewState::from_event(&msg).unwrap_or_default();
+                                focused_view = Some(view.id).filter(|_| view.focused);
+                                sender.send(view.update()).await.unwrap();
+                            }
                             let Some(Value::String(title)) = msg
                                 .get("view")
                                 .and_then(|v| v.as_object())
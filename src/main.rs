@@ -1,57 +1,108 @@
 use std::{
     any::{Any, TypeId},
+    collections::{BTreeMap, HashSet},
+    env,
     fmt::Debug,
+    iter,
     path::PathBuf,
     process::{exit, Command},
     sync::Arc,
     time::Duration,
 };
 
-use config::{anchor::BarAnchor, get_config_dir, read_config, Config, EnabledModules, Thrice};
-use fill::FillExt;
-use handlebars::Handlebars;
+use actions::{parse_action, Visibility};
+use animation::Animated;
+use autohide::{Autohide, AutohideEvent};
+use button::ButtonEvent;
+use config::{
+    anchor::BarAnchor, get_config_dir, popup_placement::PopupPlacement, read_config, Config,
+};
+use handlebars::{Handlebars, JsonValue};
+use helpers::show_when;
+use hooks::HookState;
 use iced::{
     daemon,
     platform_specific::shell::commands::{
-        layer_surface::{destroy_layer_surface, get_layer_surface, Layer},
+        layer_surface::{
+            destroy_layer_surface, get_layer_surface, set_exclusive_zone, set_layer, set_size,
+            Layer,
+        },
         output::{get_output, get_output_info, OutputInfo},
-        popup::{destroy_popup, get_popup},
     },
     runtime::platform_specific::wayland::{
         layer_surface::{IcedOutput, SctkLayerSurfaceSettings},
-        popup::{SctkPopupSettings, SctkPositioner},
+        popup::{Anchor, Gravity, SctkPopupSettings, SctkPositioner},
     },
     stream,
     theme::Palette,
-    widget::{container, stack},
+    widget::{column, container, mouse_area, row, text},
     window::Id,
-    Alignment, Color, Element, Font, Rectangle, Subscription, Task, Theme,
+    Alignment, Color, Element, Font,
+    Length::Fill,
+    Rectangle, Subscription, Task, Theme,
 };
-use list::{list, DynamicAlign};
-use listeners::register_listeners;
-use modules::{empty::EmptyModule, register_modules, Module};
+use list::list;
+use listeners::{ipc::send_command, register_listeners};
+use modules::{empty::EmptyModule, register_modules, ActionContext, CommandAction, Module};
+use popups::{PopupEvent, PopupTrigger, Popups};
 use registry::Registry;
 use resolvers::register_resolvers;
+use section::Section;
 use tokio::{
     sync::{broadcast, mpsc},
     time::sleep,
 };
+use tooltip::{module_tooltip, ElementExt};
+use variables::Variables;
 
+mod actions;
+mod animation;
+mod appearance;
+mod autohide;
 mod config;
 #[macro_use]
 mod list;
 mod button;
 mod fill;
+mod groups;
 mod helpers;
+mod hooks;
 mod listeners;
+mod marquee;
+mod menu;
 mod modules;
+mod popups;
 mod registry;
 mod resolvers;
+mod rotate;
+mod section;
+mod template_popup;
 mod tooltip;
+mod variables;
 
 const NERD_FONT: Font = Font::with_name("3270 Nerd Font");
 
 fn main() -> iced::Result {
+    // Send a command to the running instance, e.g. `bar-rs msg vars`
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Some((subcommand, command)) = args.split_first() {
+        if subcommand != "msg" {
+            eprintln!("Unknown subcommand: {subcommand}, usage: bar-rs [msg <command>]");
+            exit(1);
+        }
+        if command.is_empty() {
+            eprintln!("Missing command, usage: bar-rs msg <command>");
+            exit(1);
+        }
+        match send_command(&command.join(" ")) {
+            Ok(reply) => print!("{reply}"),
+            Err(e) => {
+                eprintln!("Failed to connect to bar-rs: {e}");
+                exit(1);
+            }
+        }
+        exit(0);
+    }
     daemon("Bar", Bar::update, Bar::view)
         .theme(Bar::theme)
         .font(include_bytes!("../assets/3270/3270NerdFont-Regular.ttf"))
@@ -69,6 +120,10 @@ fn main() -> iced::Result {
                                 .get_listeners(&state.config.enabled_listeners)
                                 .map(|l| l.subscription()),
                         )
+                        .chain(state.popups.subscription())
+                        .chain(state.animating().then(|| {
+                            iced::time::every(Duration::from_millis(16)).map(|_| Message::Animate)
+                        }))
                 })
             } else {
                 Subscription::none()
@@ -98,10 +153,15 @@ impl Debug for ActionFn {
 
 #[derive(Debug, Clone)]
 pub enum Message {
+    /// Open or close the popup of a module, which has the given bounds
     Popup {
         type_id: TypeId,
-        dimension: Rectangle<i32>,
+        size: (i32, i32),
+        bounds: Rectangle,
+        trigger: PopupTrigger,
     },
+    /// Something happened to the popup with the given id
+    PopupEvent(Id, PopupEvent),
     Update(Arc<UpdateFn>),
     Action(Arc<ActionFn>),
     GetConfig(mpsc::Sender<(Arc<PathBuf>, Arc<Config>)>),
@@ -110,6 +170,34 @@ pub enum Message {
         fn(&Registry) -> broadcast::Receiver<Arc<dyn Any + Send + Sync>>,
     ),
     Spawn(Arc<Command>),
+    /// A shell command whose `{{value}}`s are rendered with the given values, see [CommandAction]
+    SpawnTemplate(String, BTreeMap<&'static str, String>),
+    /// Several messages which are handled in order
+    Batch(Vec<Message>),
+    /// Open the popup of a module by its name, next to the given bounds
+    OpenPopup {
+        module: String,
+        bounds: Rectangle,
+    },
+    /// Open the context menu of a module by its name, or of the clicked module
+    OpenMenu {
+        module: Option<String>,
+        clicked: TypeId,
+        bounds: Rectangle,
+    },
+    ToggleModule(String),
+    /// Expand or collapse a module group by its name
+    ExpandGroup(String, Visibility),
+    SetBarVisibility(Visibility),
+    Autohide(AutohideEvent),
+    /// Check the hooks after their debounce time
+    RunHooks,
+    /// Redraw the bar and its popups while something is animated
+    Animate,
+    /// Whether the focused window is fullscreen, sent by the listeners of the compositors
+    Fullscreen(bool),
+    /// A command received on the ipc socket and the channel to send the reply to
+    Ipc(String, mpsc::Sender<String>),
     ReloadConfig,
     LoadRegistry,
     GotOutput(Option<IcedOutput>),
@@ -148,63 +236,105 @@ impl Message {
         cmd.arg(arg);
         Message::Spawn(Arc::new(cmd))
     }
+    /// Opens the popup of a module on a left click, or when it is hovered if its popup opens on
+    /// hover. Other events are left to the module wrapper.
     fn popup<'a, T>(
         width: i32,
         height: i32,
-        anchor: &BarAnchor,
     ) -> impl Fn(
-        iced::Event,
+        ButtonEvent,
         iced::core::Layout,
         iced::mouse::Cursor,
         &mut dyn iced::core::Clipboard,
         &Rectangle,
-    ) -> Message
+    ) -> Option<Message>
            + 'a
     where
         T: Module,
     {
-        let anchor = *anchor;
-        move |_: iced::Event,
+        move |event: ButtonEvent,
               layout: iced::core::Layout,
               _: iced::mouse::Cursor,
               _: &mut dyn iced::core::Clipboard,
               _: &Rectangle| {
-            let bounds = layout.bounds();
-            let position = layout.position();
-            let x = match anchor {
-                BarAnchor::Left => bounds.width as i32,
-                BarAnchor::Right => -width,
-                _ => position.x as i32,
+            let trigger = match event.hover {
+                Some(hovered) => PopupTrigger::Hover(hovered),
+                None if event.is_left_click() => PopupTrigger::Click,
+                None => return None,
             };
-            let y = match anchor {
-                BarAnchor::Top => bounds.height as i32,
-                BarAnchor::Bottom => -height,
-                _ => position.y as i32,
-            };
-            Message::Popup {
+            Some(Message::Popup {
                 type_id: TypeId::of::<T>(),
-                dimension: Rectangle {
-                    x,
-                    y,
-                    width,
-                    height,
-                },
-            }
+                size: (width, height),
+                bounds: layout.bounds(),
+                trigger,
+            })
         }
     }
 }
 
+/// Places a popup of the given size next to the bounds of its module. The compositor moves it
+/// according to `screen_edge` if it doesn't fit on the output.
+fn popup_positioner(
+    anchor: &BarAnchor,
+    placement: &PopupPlacement,
+    (width, height): (i32, i32),
+    bounds: Rectangle,
+) -> SctkPositioner {
+    use Alignment::*;
+    // The point of the module the popup is attached to, and the direction it extends to
+    let (anchor, gravity) = match (anchor, placement.align) {
+        (BarAnchor::Top, Start) => (Anchor::BottomLeft, Gravity::BottomRight),
+        (BarAnchor::Top, Center) => (Anchor::Bottom, Gravity::Bottom),
+        (BarAnchor::Top, End) => (Anchor::BottomRight, Gravity::BottomLeft),
+        (BarAnchor::Bottom, Start) => (Anchor::TopLeft, Gravity::TopRight),
+        (BarAnchor::Bottom, Center) => (Anchor::Top, Gravity::Top),
+        (BarAnchor::Bottom, End) => (Anchor::TopRight, Gravity::TopLeft),
+        (BarAnchor::Left, Start) => (Anchor::TopRight, Gravity::BottomRight),
+        (BarAnchor::Left, Center) => (Anchor::Right, Gravity::Right),
+        (BarAnchor::Left, End) => (Anchor::BottomRight, Gravity::TopRight),
+        (BarAnchor::Right, Start) => (Anchor::TopLeft, Gravity::BottomLeft),
+        (BarAnchor::Right, Center) => (Anchor::Left, Gravity::Left),
+        (BarAnchor::Right, End) => (Anchor::BottomLeft, Gravity::TopLeft),
+    };
+    SctkPositioner {
+        size: Some((width as u32, height as u32)),
+        anchor_rect: Rectangle {
+            x: bounds.x as i32,
+            y: bounds.y as i32,
+            width: bounds.width as i32,
+            height: bounds.height as i32,
+        },
+        anchor,
+        gravity,
+        constraint_adjustment: placement.screen_edge,
+        offset: (placement.offset_x, placement.offset_y),
+        ..Default::default()
+    }
+}
+
 #[derive(Debug)]
 struct Bar<'a> {
     config_file: Arc<PathBuf>,
     config: Arc<Config>,
     registry: Registry,
-    logical_size: Option<(u32, u32)>,
     output: IcedOutput,
     layer_id: Id,
     open: bool,
-    popup: Option<(TypeId, Id)>,
+    popups: Popups,
     templates: Handlebars<'a>,
+    /// Modules hidden by `toggle:module`
+    hidden_modules: HashSet<String>,
+    /// Module groups which are expanded
+    expanded_groups: HashSet<String>,
+    /// Whether the bar was hidden by `bar:hide`
+    hidden: bool,
+    variables: Variables,
+    hook_states: Vec<HookState>,
+    autohide: Autohide,
+    /// Whether a module reports a fullscreen window
+    fullscreen: bool,
+    /// The background color, which changes according to the `[appearance]` rules
+    background: Animated<Color>,
 }
 
 impl Bar<'_> {
@@ -215,9 +345,14 @@ impl Bar<'_> {
         register_resolvers(&mut registry);
 
         let mut templates = Handlebars::new();
+        helpers::register_helpers(&mut templates);
+        let variables = Variables::default();
+        templates.register_helper("var", Box::new(variables.helper()));
 
         let config_file = get_config_dir();
         let config = read_config(&config_file, &mut registry, &mut templates);
+        let mut autohide = Autohide::default();
+        autohide.reset(&config.autohide);
 
         ctrlc::set_handler(|| {
             println!("Received exit signal...Exiting");
@@ -225,17 +360,26 @@ impl Bar<'_> {
         })
         .unwrap();
 
+        let background = Animated::new(config.module_config.global.background_color);
         let bar = Self {
             config_file: config_file.into(),
             config: config.into(),
             registry,
-            logical_size: None,
             output: IcedOutput::Active,
             layer_id: Id::unique(),
             open: true,
-            popup: None,
+            popups: Popups::default(),
             templates,
+            hidden_modules: HashSet::new(),
+            expanded_groups: HashSet::new(),
+            hidden: false,
+            variables,
+            hook_states: vec![],
+            autohide,
+            fullscreen: false,
+            background,
         };
+        bar.variables.publish(&bar.registry, &bar.config);
         let task = match &bar.config.monitor {
             Some(_) => bar.try_get_output(),
             None => bar.open(),
@@ -245,41 +389,88 @@ impl Bar<'_> {
     }
 
     fn update(&mut self, msg: Message) -> Task<Message> {
+        // Only these messages change the state of the modules
+        let changed = matches!(
+            msg,
+            Message::Update(_) | Message::ReloadConfig | Message::LoadRegistry
+        );
+        // Hooks and `[appearance]` rules only need to be checked when a value might have changed,
+        // or after the debounce time of a hook
+        let run_hooks = changed || matches!(msg, Message::RunHooks);
+        let task = self.handle_message(msg);
+        self.popups.refresh(&self.config.popup_config);
+        if !run_hooks {
+            return task;
+        }
+        if changed {
+            self.variables.publish(&self.registry, &self.config);
+        }
+        if self.config.hooks.is_empty() && self.config.appearance.is_empty() {
+            self.background
+                .reset(self.config.module_config.global.background_color);
+            return task;
+        }
+        self.update_background();
+        let hooks = self.config.hooks.run(
+            &mut self.hook_states,
+            &self.variables,
+            &self.registry,
+            &self.config,
+            &self.templates,
+        );
+        Task::batch([task, hooks])
+    }
+
+    /// Fade to the background of the first `[appearance]` rule which applies, `variables` have
+    /// to be up to date
+    fn update_background(&mut self) {
+        let appearance = &self.config.appearance;
+        let background = match appearance.is_empty() {
+            true => None,
+            false => appearance.background(&self.variables.json(), &self.templates),
+        };
+        self.background
+            .configure(&appearance.animation(&self.config.animation));
+        self.background
+            .set(background.unwrap_or(self.config.module_config.global.background_color));
+    }
+
+    /// Whether an animation is running, in which case the bar is redrawn every frame
+    fn animating(&self) -> bool {
+        self.background.is_animating()
+            || self.popups.animating()
+            || self
+                .registry
+                .get_modules(self.config.enabled_modules.get_all(), &self.config)
+                .any(|m| m.animating())
+    }
+
+    fn handle_message(&mut self, msg: Message) -> Task<Message> {
         match msg {
-            Message::Popup { type_id, dimension } => {
+            Message::Popup {
+                type_id,
+                size,
+                bounds,
+                trigger,
+            } => {
+                let name = self.registry.get_module_by_id(type_id).name();
+                let behavior = self.config.popup_behavior(&name);
+                let placement = self.config.popup_placement(&name);
+                let parent = self.layer_id;
+                let positioner = popup_positioner(&self.config.anchor, &placement, size, bounds);
                 let settings = |id| SctkPopupSettings {
-                    parent: self.layer_id,
+                    parent,
                     id,
-                    positioner: SctkPositioner {
-                        size: Some((dimension.width as u32, dimension.height as u32)),
-                        anchor_rect: Rectangle {
-                            x: dimension.x,
-                            y: dimension.y,
-                            width: dimension.width,
-                            height: dimension.height,
-                        },
-                        ..Default::default()
-                    },
+                    positioner,
                     parent_size: None,
                     grab: true,
                 };
-                return match self.popup {
-                    None => {
-                        let id = Id::unique();
-                        self.popup = Some((type_id, id));
-                        get_popup(settings(id))
-                    }
-                    Some((old_ty_id, id)) => match old_ty_id == type_id {
-                        true => {
-                            self.popup = None;
-                            destroy_popup(id)
-                        }
-                        false => {
-                            self.popup = Some((type_id, id));
-                            destroy_popup(id).chain(get_popup(settings(id)))
-                        }
-                    },
-                };
+                return self
+                    .popups
+                    .trigger(type_id, trigger, behavior, settings, &self.config);
+            }
+            Message::PopupEvent(id, event) => {
+                return self.popups.handle(id, event, &self.config);
             }
             Message::Update(task) => {
                 Arc::into_inner(task).unwrap().0(&mut self.registry);
@@ -298,6 +489,159 @@ impl Bar<'_> {
                     .inspect_err(|e| eprintln!("Failed to spawn command: {e}"))
                     .ok();
             }
+            Message::SpawnTemplate(command, vars) => {
+                CommandAction::command(&command, &vars, &mut self.templates)
+                    .spawn()
+                    .inspect_err(|e| eprintln!("Failed to spawn command: {e}"))
+                    .ok();
+            }
+            Message::Batch(msgs) => {
+                return Task::batch(
+                    msgs.into_iter()
+                        .map(|msg| self.update(msg))
+                        .collect::<Vec<_>>(),
+                )
+            }
+            Message::OpenPopup { module, bounds } => {
+                let Some((type_id, size)) = self
+                    .registry
+                    .resolve(&module, Some(&self.config))
+                    .and_then(|id| self.popup_size(id).map(|size| (id, size)))
+                else {
+                    eprintln!("There is no module named {module} which has a popup");
+                    return Task::none();
+                };
+                return self.update(Message::Popup {
+                    type_id,
+                    size,
+                    bounds,
+                    trigger: PopupTrigger::Action,
+                });
+            }
+            Message::OpenMenu {
+                module,
+                clicked,
+                bounds,
+            } => {
+                let type_id = match module {
+                    Some(module) => match self.registry.resolve(&module, Some(&self.config)) {
+                        Some(id) => id,
+                        None => {
+                            eprintln!("No Module named {module} is registered");
+                            return Task::none();
+                        }
+                    },
+                    None => clicked,
+                };
+                let module = self.registry.get_module_by_id(type_id);
+                let name = module.name();
+                let entries = module.menu();
+                let configured = self
+                    .config
+                    .menus
+                    .get(&name)
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                if entries.is_empty() && configured.is_empty() {
+                    eprintln!("The module {name} doesn't have a context menu");
+                    return Task::none();
+                }
+                let size = menu::menu_size(configured, &entries, &self.config.popup_config);
+                let placement = self.config.popup_placement(&name);
+                let parent = self.layer_id;
+                let positioner = popup_positioner(&self.config.anchor, &placement, size, bounds);
+                let settings = |id| SctkPopupSettings {
+                    parent,
+                    id,
+                    positioner,
+                    parent_size: None,
+                    grab: true,
+                };
+                return self
+                    .popups
+                    .toggle_menu(type_id, entries, settings, &self.config);
+            }
+            Message::ToggleModule(module) => {
+                let Some(id) = self.registry.resolve(&module, Some(&self.config)) else {
+                    eprintln!("No Module named {module} is registered");
+                    return Task::none();
+                };
+                let name = self.registry.get_module_by_id(id).name();
+                if !self.hidden_modules.remove(&name) {
+                    self.hidden_modules.insert(name);
+                }
+            }
+            Message::ExpandGroup(group, visibility) => {
+                let expanded = match visibility {
+                    Visibility::Show => true,
+                    Visibility::Hide => false,
+                    Visibility::Toggle => !self.expanded_groups.contains(&group),
+                };
+                match expanded {
+                    true => self.expanded_groups.insert(group),
+                    false => self.expanded_groups.remove(&group),
+                };
+            }
+            Message::SetBarVisibility(visibility) => {
+                let hidden = match visibility {
+                    Visibility::Show => false,
+                    Visibility::Hide => true,
+                    Visibility::Toggle => !self.hidden,
+                };
+                if hidden == self.hidden {
+                    return Task::none();
+                }
+                self.hidden = hidden;
+                return match hidden {
+                    true => self
+                        .popups
+                        .close_all()
+                        .chain(destroy_layer_surface(self.layer_id)),
+                    false => self.open(),
+                };
+            }
+            Message::Autohide(event) => {
+                let (reveal, task) =
+                    self.autohide
+                        .handle(event, &self.config.autohide, !self.popups.is_empty());
+                return match reveal {
+                    Some(_) => Task::batch([self.resize(), task]),
+                    None => task,
+                };
+            }
+            Message::RunHooks | Message::Animate => {}
+            Message::Fullscreen(fullscreen) => {
+                if fullscreen == self.fullscreen {
+                    return Task::none();
+                }
+                self.fullscreen = fullscreen;
+                if self.open && !self.hidden {
+                    return set_layer(self.layer_id, self.layer());
+                }
+            }
+            Message::Ipc(command, reply) => {
+                let mut task = Task::none();
+                let answer = match command.split_once(' ').unwrap_or((command.as_str(), "")) {
+                    ("vars", _) => self.variables.dump(),
+                    ("var", key) => match self.variables.get(key.trim()) {
+                        Some(value) => format!("{value}\n"),
+                        None => format!("There is no variable named {}\n", key.trim()),
+                    },
+                    ("action", action) => {
+                        task = Task::done(parse_action(action).as_message(&ActionContext {
+                            module: TypeId::of::<EmptyModule>(),
+                            bounds: Rectangle::default(),
+                            vars: BTreeMap::new(),
+                        }));
+                        String::new()
+                    }
+                    _ => format!("Unknown command: {command}\n"),
+                };
+                reply
+                    .try_send(answer)
+                    .unwrap_or_else(|e| eprintln!("Failed to reply to an ipc command: {e}"));
+                return task;
+            }
             Message::ReloadConfig => {
                 println!(
                     "Reloading config from {}",
@@ -305,12 +649,16 @@ impl Bar<'_> {
                 );
                 self.config =
                     read_config(&self.config_file, &mut self.registry, &mut self.templates).into();
+                self.hook_states.clear();
+                self.autohide.reset(&self.config.autohide);
                 if self.config.hard_reload {
                     self.open = false;
+                    self.hidden = false;
                     return destroy_layer_surface(self.layer_id)
                         .chain(self.open())
                         .chain(Task::done(Message::LoadRegistry));
                 }
+                return self.resize().chain(set_layer(self.layer_id, self.layer()));
             }
             Message::LoadRegistry => {
                 self.registry = Registry::default();
@@ -319,6 +667,8 @@ impl Bar<'_> {
                 register_resolvers(&mut self.registry);
                 self.config =
                     read_config(&self.config_file, &mut self.registry, &mut self.templates).into();
+                self.hook_states.clear();
+                self.autohide.reset(&self.config.autohide);
                 self.open = true;
             }
             Message::GotOutput(optn) => {
@@ -335,10 +685,7 @@ impl Bar<'_> {
             }
             Message::GotOutputInfo(optn) => {
                 return match optn {
-                    Some(info) => {
-                        self.logical_size = info.logical_size.map(|(x, y)| (x as u32, y as u32));
-                        self.open()
-                    }
+                    Some(_) => self.open(),
                     None => Task::stream(stream::channel(1, |_| async {
                         sleep(Duration::from_millis(500)).await;
                     }))
@@ -352,88 +699,268 @@ impl Bar<'_> {
     fn view(&self, window_id: Id) -> Element<'_, Message> {
         if window_id == self.layer_id {
             self.bar_view()
-        } else if let Some(mod_id) = self
-            .popup
-            .and_then(|(m_id, p_id)| (p_id == window_id).then_some(m_id))
-        {
-            self.registry.get_module_by_id(mod_id).popup_wrapper(
-                &self.config.popup_config,
-                &self.config.anchor,
-                &self.templates,
-            )
+        } else if let Some((mod_id, config, menu)) = self.popups.get(window_id) {
+            let hover = move |hovered| Message::PopupEvent(window_id, PopupEvent::Hover(hovered));
+            let module = self.registry.get_module_by_id(mod_id);
+            let name = module.name();
+            let popup = match (menu, self.config.template_popups.get(&name)) {
+                (Some(menu), _) => {
+                    let mut vars = module.context(None);
+                    let configured = self.config.menus.get(&name);
+                    vars.insert("module", name);
+                    menu::view(
+                        menu,
+                        configured.map(Vec::as_slice).unwrap_or_default(),
+                        window_id,
+                        mod_id,
+                        vars,
+                        config,
+                        &self.config.anchor,
+                    )
+                }
+                (None, Some(popup)) => popup.view(
+                    module,
+                    mod_id,
+                    config,
+                    &self.config.anchor,
+                    &self.variables.json(),
+                    &self.templates,
+                ),
+                (None, None) => module.popup_wrapper(config, &self.config.anchor, &self.templates),
+            };
+            mouse_area(popup)
+                .on_enter(hover(true))
+                .on_exit(hover(false))
+                .into()
         } else {
             "Internal error".into()
         }
     }
 
+    /// The size of the popup of a module, if it has one. A `template` replaces the popup of the
+    /// module itself.
+    fn popup_size(&self, id: TypeId) -> Option<(i32, i32)> {
+        let module = self.registry.get_module_by_id(id);
+        match self.config.template_popups.get(&module.name()) {
+            Some(popup) => Some(popup.size(&self.config.popup_config)),
+            None => module.popup_size(&self.config.popup_config),
+        }
+    }
+
     fn bar_view(&self) -> Element<'_, Message> {
+        let hover = |hovered| Message::Autohide(AutohideEvent::Hover(hovered));
+        if !self.autohide.revealed {
+            return mouse_area(container("").width(Fill).height(Fill))
+                .on_enter(hover(true))
+                .into();
+        }
         let anchor = &self.config.anchor;
-        let make_list = |spacing: fn(&Thrice<f32>) -> f32,
-                         field: fn(&EnabledModules) -> &Vec<String>| {
-            let modules = self
-                .registry
-                .get_modules(field(&self.config.enabled_modules).iter(), &self.config)
-                .filter(|&m| m.active())
-                .map(|m| {
-                    m.wrapper(
+        let state = match self.config.show_when.is_empty() && self.config.tooltips.is_empty() {
+            true => JsonValue::Null,
+            false => self.variables.json(),
+        };
+        let global = &self.config.module_config.global;
+        let sections = self.config.enabled_modules.sections.iter().map(|section| {
+            let spacing = section.spacing.unwrap_or(match section.align {
+                Alignment::Start => global.spacing.left,
+                Alignment::Center => global.spacing.center,
+                Alignment::End => global.spacing.right,
+            });
+            let modules = section
+                .modules
+                .iter()
+                .filter_map(|name| match self.config.enabled_modules.group(name) {
+                    Some(group) => {
+                        let modules = self
+                            .module_views(group.modules.iter(), &state)
+                            .into_iter()
+                            .map(|(module, _)| module)
+                            .collect::<Vec<_>>();
+                        (!modules.is_empty()).then(|| {
+                            let view = group.view(
+                                modules,
+                                self.expanded_groups.contains(&group.name),
+                                &self.config.module_config.local,
+                                spacing,
+                                anchor,
+                            );
+                            (view, group.priority)
+                        })
+                    }
+                    None => self.module_views(iter::once(name), &state).pop(),
+                })
+                .collect::<Vec<_>>();
+            let content = if modules.is_empty() {
+                vec![(
+                    self.registry.get_module::<EmptyModule>().wrapper(
                         &self.config.module_config.local,
+                        "".into(),
+                        anchor,
+                    ),
+                    0,
+                )]
+            } else {
+                modules
+            };
+            let view = Section::new(anchor, content)
+                .align(section.align)
+                .weight(section.weight)
+                .overflow(section.overflow)
+                .spacing(spacing);
+            (section.row, Element::from(view))
+        });
+        let mut rows = iter::repeat_with(Vec::new)
+            .take(self.config.enabled_modules.rows())
+            .collect::<Vec<_>>();
+        for (i, section) in sections {
+            rows[i].push(section);
+        }
+        // Rows are stacked across the bar and share its thickness
+        let rows = rows.into_iter().map(|sections| {
+            Element::from(container(list(anchor, sections)).width(Fill).height(Fill))
+        });
+        let bar = container(match anchor.vertical() {
+            true => Element::from(row(rows)),
+            false => column(rows).into(),
+        })
+        .padding(global.padding);
+        match self.config.autohide.enabled {
+            true => mouse_area(bar)
+                .on_enter(hover(true))
+                .on_exit(hover(false))
+                .into(),
+            false => bar.into(),
+        }
+    }
+
+    /// The views of the modules with the given names which are visible, with their priority
+    fn module_views<'b>(
+        &'b self,
+        names: impl Iterator<Item = &'b String>,
+        state: &JsonValue,
+    ) -> Vec<(Element<'b, Message>, i32)> {
+        let anchor = &self.config.anchor;
+        let local_config = &self.config.module_config.local;
+        self.registry
+            .get_modules(names, &self.config)
+            .filter(|&m| m.active() && !self.hidden_modules.contains(&m.name()))
+            .filter(|&m| show_when(m.as_ref(), state, &self.config, &self.templates))
+            .map(|m| {
+                let view = m
+                    .wrapper(
+                        local_config,
                         m.view(
-                            &self.config.module_config.local,
+                            local_config,
                             &self.config.popup_config,
                             anchor,
                             &self.templates,
                         ),
                         anchor,
                     )
-                })
-                .collect::<Vec<_>>();
-            let content = if modules.is_empty() {
-                vec![self.registry.get_module::<EmptyModule>().wrapper(
-                    &self.config.module_config.local,
-                    "".into(),
-                    anchor,
-                )]
-            } else {
-                modules
-            };
-            container(
-                list(anchor, content).spacing(spacing(&self.config.module_config.global.spacing)),
-            )
-            .fillx(!anchor.vertical())
+                    .tooltip_maybe(
+                        module_tooltip(m.as_ref(), state, &self.config, &self.templates)
+                            .map(|tooltip| text(tooltip).size(local_config.tooltip.font_size)),
+                        &local_config.tooltip,
+                        anchor,
+                    );
+                let priority = self.config.priorities.get(&m.name()).copied();
+                (view, priority.unwrap_or_default())
+            })
+            .collect()
+    }
+
+    /// The size of the bar, `None` stretches it along its edge. It is only a strip while it is
+    /// hidden by `autohide`.
+    fn size(&self) -> (Option<u32>, Option<u32>) {
+        let global = &self.config.module_config.global;
+        let thickness = match self.autohide.revealed {
+            true => self.thickness(),
+            false => self.config.autohide.trigger_size,
         };
-        let left = make_list(|s| s.left, |m| &m.left);
-        let center = make_list(|s| s.center, |m| &m.center);
-        let right = make_list(|s| s.right, |m| &m.right);
-        container(stack!(
-            center.align(anchor, Alignment::Center),
-            list(
-                anchor,
-                [(left, Alignment::Start), (right, Alignment::End)]
-                    .map(|(e, align)| e.align(anchor, align).into())
-            )
+        match self.config.anchor.vertical() {
+            true => (Some(thickness), global.height),
+            false => (global.width, Some(thickness)),
+        }
+    }
+
+    /// The size of the bar across its edge, while it is revealed
+    fn thickness(&self) -> u32 {
+        let global = &self.config.module_config.global;
+        let configured = match self.config.anchor.vertical() {
+            true => global.width,
+            false => global.height,
+        };
+        match global.auto_thickness {
+            true => self.auto_thickness(),
+            false => configured.unwrap_or(30),
+        }
+    }
+
+    /// The thickness which fits the largest module of every row
+    fn auto_thickness(&self) -> u32 {
+        let anchor = &self.config.anchor;
+        let local = &self.config.module_config.local;
+        let enabled = &self.config.enabled_modules;
+        let module = |name: &String| {
+            self.registry
+                .resolve(name, Some(&self.config))
+                .map(|id| self.registry.get_module_by_id(id).thickness(local, anchor))
+                .unwrap_or_default()
+        };
+        let rows = (0..enabled.rows())
+            .map(|row| {
+                enabled
+                    .sections
+                    .iter()
+                    .filter(|section| section.row == row)
+                    .flat_map(|section| section.modules.iter())
+                    .map(|name| match enabled.group(name) {
+                        Some(group) => group.thickness(
+                            group.modules.iter().map(module).fold(0., f32::max),
+                            local,
+                            anchor,
+                        ),
+                        None => module(name),
+                    })
+                    .fold(0., f32::max)
+            })
+            .sum::<f32>();
+        let padding = self.config.module_config.global.padding;
+        let padding = match anchor.vertical() {
+            true => padding.horizontal(),
+            false => padding.vertical(),
+        };
+        (rows + padding).ceil() as u32
+    }
+
+    /// Apply the size and exclusive zone after the bar was revealed, hidden or reconfigured
+    fn resize(&self) -> Task<Message> {
+        let (width, height) = self.size();
+        set_size(self.layer_id, width, height).chain(set_exclusive_zone(
+            self.layer_id,
+            self.config
+                .exclusive_zone(self.autohide.revealed.then(|| self.thickness())),
         ))
-        .padding(self.config.module_config.global.padding)
-        .into()
+    }
+
+    /// The layer of the bar, which moves behind fullscreen windows if `fullscreen_layer` is set
+    fn layer(&self) -> Layer {
+        match (self.fullscreen, self.config.fullscreen_layer) {
+            (true, Some(layer)) => layer,
+            _ => self.config.layer,
+        }
     }
 
     fn open(&self) -> Task<Message> {
-        let (x, y) = self.logical_size.unwrap_or((1920, 1080));
-        let (width, height) = match self.config.anchor.vertical() {
-            true => (
-                self.config.module_config.global.width.unwrap_or(30),
-                self.config.module_config.global.height.unwrap_or(y),
-            ),
-            false => (
-                self.config.module_config.global.width.unwrap_or(x),
-                self.config.module_config.global.height.unwrap_or(30),
-            ),
-        };
+        let (width, height) = self.size();
         get_layer_surface(SctkLayerSurfaceSettings {
-            layer: Layer::Top,
+            layer: self.layer(),
             keyboard_interactivity: self.config.kb_focus,
             anchor: (&self.config.anchor).into(),
-            exclusive_zone: self.config.exclusive_zone(),
-            size: Some((Some(width), Some(height))),
+            exclusive_zone: self
+                .config
+                .exclusive_zone(self.autohide.revealed.then(|| self.thickness())),
+            size: Some((width, height)),
             namespace: "bar-rs".to_string(),
             output: self.output.clone(),
             margin: self.config.module_config.global.margin,
@@ -476,16 +1003,13 @@ impl Bar<'_> {
     }
 
     fn theme(&self, window_id: Id) -> Theme {
-        if let Some(mod_id) = self
-            .popup
-            .and_then(|(m_id, p_id)| (p_id == window_id).then_some(m_id))
-        {
+        if let Some((mod_id, ..)) = self.popups.get(window_id) {
             self.registry.get_module_by_id(mod_id).popup_theme()
         } else {
             Theme::custom(
                 "Bar theme".to_string(),
                 Palette {
-                    background: self.config.module_config.global.background_color,
+                    background: self.background.value(),
                     text: Color::WHITE,
                     primary: Color::WHITE,
                     success: Color::WHITE,
@@ -0,0 +1,247 @@
+use iced::{
+    alignment::{Horizontal, Vertical},
+    core::{
+        event, layout, mouse, renderer,
+        text::{self, Paragraph},
+        widget::{tree, Tree},
+        Clipboard, Layout, Shell, Widget,
+    },
+    time::{Duration, Instant},
+    widget::text::{LineHeight, Shaping, Wrapping},
+    window, Color, Element, Event, Font, Length, Point, Rectangle, Size,
+};
+
+use crate::config::marquee_config::MarqueeConfig;
+
+/// A single line of text which scrolls back and forth if it is longer than the configured width.
+/// It pauses at either end and stops while it is hovered.
+pub struct Marquee {
+    content: String,
+    size: f32,
+    color: Color,
+    font: Font,
+    config: MarqueeConfig,
+    /// Whether the bar is vertical, in which case the marquee doesn't fill the height
+    vertical: bool,
+}
+
+impl Marquee {
+    pub fn new(content: String, size: f32, color: Color, config: MarqueeConfig) -> Self {
+        Self {
+            content,
+            size,
+            color,
+            font: Font::DEFAULT,
+            config,
+            vertical: false,
+        }
+    }
+
+    pub fn font(mut self, font: Font) -> Self {
+        self.font = font;
+        self
+    }
+
+    pub fn vertical(mut self, vertical: bool) -> Self {
+        self.vertical = vertical;
+        self
+    }
+
+    fn text<T>(&self, content: T, bounds: Size) -> text::Text<T, Font> {
+        text::Text {
+            content,
+            bounds,
+            size: self.size.into(),
+            line_height: LineHeight::default(),
+            font: self.font,
+            horizontal_alignment: Horizontal::Left,
+            vertical_alignment: Vertical::Center,
+            shaping: Shaping::Advanced,
+            wrapping: Wrapping::None,
+        }
+    }
+
+    fn height(&self) -> Length {
+        match self.vertical {
+            true => Length::Shrink,
+            false => Length::Fill,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The width of the whole text
+    text_width: f32,
+    /// How far the text is scrolled
+    offset: f32,
+    /// Whether the text scrolls back to its start
+    backwards: bool,
+    /// The end of the pause at either end
+    paused_until: Option<Instant>,
+    /// The time of the last frame, `None` if the marquee is stopped
+    last_frame: Option<Instant>,
+    hovered: bool,
+}
+
+impl State {
+    /// Move the text according to the time since the last frame. Returns when the next frame
+    /// should be drawn, if the text moves.
+    fn advance(&mut self, now: Instant, overflow: f32, config: &MarqueeConfig) -> Option<Instant> {
+        if overflow <= 0. || self.hovered {
+            self.last_frame = None;
+            return None;
+        }
+        let Some(last_frame) = self.last_frame.replace(now) else {
+            // Pause at the start of the text before it scrolls
+            self.paused_until = Some(now + config.pause);
+            return self.paused_until;
+        };
+        if let Some(until) = self.paused_until {
+            if now < until {
+                return Some(until);
+            }
+            self.paused_until = None;
+            return Some(now);
+        }
+        let distance = (now - last_frame).as_secs_f32() * config.speed;
+        self.offset = match self.backwards {
+            true => self.offset - distance,
+            false => self.offset + distance,
+        }
+        .clamp(0., overflow);
+        if self.offset == 0. || self.offset == overflow {
+            self.backwards = !self.backwards;
+            self.paused_until = Some(now + config.pause);
+            return self.paused_until;
+        }
+        Some(now + Duration::from_millis(16))
+    }
+}
+
+impl<Message, Theme, Renderer> Widget<Message, Theme, Renderer> for Marquee
+where
+    Renderer: text::Renderer<Font = Font>,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn size(&self) -> Size<Length> {
+        Size {
+            width: Length::Shrink,
+            height: self.height(),
+        }
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        let bounds =
+            Renderer::Paragraph::with_text(self.text(self.content.as_str(), Size::INFINITY))
+                .min_bounds();
+        let state = tree.state.downcast_mut::<State>();
+        if bounds.width != state.text_width {
+            // The text changed, start from the beginning
+            *state = State {
+                text_width: bounds.width,
+                hovered: state.hovered,
+                ..Default::default()
+            };
+        }
+        layout::Node::new(limits.resolve(
+            Length::Shrink,
+            self.height(),
+            Size::new(bounds.width.min(self.config.width), bounds.height),
+        ))
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        _viewport: &Rectangle,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+        let bounds = layout.bounds();
+        match event {
+            Event::Window(window::Event::RedrawRequested(now)) => {
+                if let Some(next) =
+                    state.advance(now, state.text_width - bounds.width, &self.config)
+                {
+                    shell.request_redraw(window::RedrawRequest::At(next));
+                }
+            }
+            Event::Mouse(mouse::Event::CursorMoved { .. } | mouse::Event::CursorLeft) => {
+                let hovered = cursor.is_over(bounds);
+                if hovered != state.hovered {
+                    state.hovered = hovered;
+                    // Continue scrolling after the cursor left
+                    shell.request_redraw(window::RedrawRequest::NextFrame);
+                }
+            }
+            _ => {}
+        }
+        event::Status::Ignored
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        let state = tree.state.downcast_ref::<State>();
+        let bounds = layout.bounds();
+        let Some(clip_bounds) = bounds.intersection(viewport) else {
+            return;
+        };
+        renderer.fill_text(
+            self.text(
+                self.content.clone(),
+                Size::new(f32::INFINITY, bounds.height),
+            ),
+            Point::new(bounds.x - state.offset, bounds.center_y()),
+            self.color,
+            clip_bounds,
+        );
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<Marquee> for Element<'a, Message, Theme, Renderer>
+where
+    Renderer: text::Renderer<Font = Font> + 'a,
+{
+    fn from(marquee: Marquee) -> Self {
+        Self::new(marquee)
+    }
+}
+
+/// Cut text which is longer than `max_length` characters, used if the marquee is disabled
+pub fn trim(text: &str, max_length: usize) -> String {
+    match text.chars().count() > max_length {
+        true => format!(
+            "{}...",
+            text.chars()
+                .take(max_length.saturating_sub(3))
+                .collect::<String>()
+        ),
+        false => text.to_string(),
+    }
+}
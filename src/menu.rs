@@ -0,0 +1,355 @@
+use std::{
+    any::TypeId,
+    collections::{BTreeMap, HashMap},
+};
+
+use iced::{
+    core::{Clipboard, Layout},
+    mouse,
+    widget::{
+        button::{Status, Style as ButtonStyle},
+        column, container,
+        container::Style,
+        horizontal_space, row, text, Space,
+    },
+    window::Id,
+    Background, Border, Element, Event,
+    Length::Fill,
+    Rectangle,
+};
+
+use crate::{
+    actions::{parse_action, BuiltinAction},
+    button::{button, ButtonEvent},
+    config::{anchor::BarAnchor, popup_config::PopupConfig},
+    modules::{align_popup, Action, ActionContext, CommandAction},
+    popups::PopupEvent,
+    Message,
+};
+
+/// The padding of the entries of a menu
+const ENTRY_PADDING: [u16; 2] = [4, 10];
+/// The space above and below a separator
+const SEPARATOR_MARGIN: u16 = 4;
+
+/// Opens the context menu of the clicked module
+pub static OPEN_MENU: BuiltinAction = BuiltinAction::Menu(None);
+static SEPARATOR: MenuEntry = MenuEntry::Separator;
+
+/// An entry of a context menu
+#[derive(Debug)]
+pub enum MenuEntry {
+    Item {
+        label: String,
+        action: Box<dyn Action>,
+    },
+    Separator,
+    Submenu {
+        label: String,
+        entries: Vec<MenuEntry>,
+    },
+}
+
+impl MenuEntry {
+    pub fn item(label: impl Into<String>, action: impl Action) -> Self {
+        MenuEntry::Item {
+            label: label.into(),
+            action: Box::new(action),
+        }
+    }
+
+    /// An entry which runs a shell command
+    pub fn command(label: impl Into<String>, command: impl Into<String>) -> Self {
+        Self::item(label, CommandAction(command.into()))
+    }
+
+    fn label(&self) -> &str {
+        match self {
+            MenuEntry::Item { label, .. } | MenuEntry::Submenu { label, .. } => label,
+            MenuEntry::Separator => "",
+        }
+    }
+
+    fn height(&self, config: &PopupConfig) -> f32 {
+        match self {
+            MenuEntry::Separator => 1. + 2. * SEPARATOR_MARGIN as f32,
+            _ => entry_height(config),
+        }
+    }
+}
+
+fn entry_height(config: &PopupConfig) -> f32 {
+    config.font_size * 1.3 + 2. * ENTRY_PADDING[0] as f32
+}
+
+/// Modules which add entries to their context menu open it on a right click by default
+#[derive(Debug, Default)]
+pub struct MenuOnRightClick;
+
+impl MenuOnRightClick {
+    pub fn event(&self, event: &ButtonEvent) -> Option<&dyn Action> {
+        match event.event {
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Right))
+                if event.scroll.is_none() =>
+            {
+                Some(&OPEN_MENU)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Read the context menu of a module from its `[module_menu:<name>]` section. Entries are
+/// numbered, `3.1` is the first entry of the submenu `3`.
+pub fn read_menu(module: &str, config: &HashMap<String, Option<String>>) -> Vec<MenuEntry> {
+    let items = config
+        .iter()
+        .filter_map(|(key, value)| {
+            let number = key
+                .split('.')
+                .map(|n| n.trim().parse())
+                .collect::<Result<Vec<u32>, _>>()
+                .ok()?;
+            Some((number, value.as_deref().unwrap_or_default()))
+        })
+        .collect();
+    entries(module, &[], &items)
+}
+
+/// The entries of the submenu with the given number
+fn entries(module: &str, parent: &[u32], items: &BTreeMap<Vec<u32>, &str>) -> Vec<MenuEntry> {
+    items
+        .iter()
+        .filter(|(number, _)| number.len() == parent.len() + 1 && number.starts_with(parent))
+        .filter_map(|(number, value)| {
+            let has_entries = items
+                .keys()
+                .any(|other| other.len() > number.len() && other.starts_with(number));
+            if has_entries {
+                // The label of a submenu may be followed by an action, which is ignored
+                let label = value.split('|').next().unwrap_or_default().trim();
+                return Some(MenuEntry::Submenu {
+                    label: label.to_string(),
+                    entries: entries(module, number, items),
+                });
+            }
+            if value.trim() == "---" {
+                return Some(MenuEntry::Separator);
+            }
+            let Some((label, action)) = value.split_once('|') else {
+                eprintln!("Invalid menu entry of {module}: {value}, expected label | action");
+                return None;
+            };
+            Some(MenuEntry::Item {
+                label: label.trim().to_string(),
+                action: parse_action(action.trim()),
+            })
+        })
+        .collect()
+}
+
+/// The state of an open context menu
+#[derive(Debug, Default)]
+pub struct OpenMenu {
+    /// The entries the module added when the menu was opened
+    entries: Vec<MenuEntry>,
+    /// The indices of the submenus which are open, starting at the top of the menu
+    path: Vec<usize>,
+}
+
+impl OpenMenu {
+    pub fn new(entries: Vec<MenuEntry>) -> Self {
+        Self {
+            entries,
+            path: vec![],
+        }
+    }
+
+    /// Open the submenu with the given index, or go back to the parent menu
+    pub fn navigate(&mut self, submenu: Option<usize>) {
+        match submenu {
+            Some(index) => self.path.push(index),
+            None => {
+                self.path.pop();
+            }
+        }
+    }
+
+    /// The entries of the open submenu and its label, if a submenu is open
+    fn current<'a>(&'a self, configured: &'a [MenuEntry]) -> (Vec<&'a MenuEntry>, Option<&'a str>) {
+        let mut entries = top(configured, &self.entries);
+        let mut label = None;
+        for &index in &self.path {
+            let Some(MenuEntry::Submenu {
+                label: submenu,
+                entries: submenu_entries,
+            }) = entries.get(index).copied()
+            else {
+                break;
+            };
+            label = Some(submenu.as_str());
+            entries = submenu_entries.iter().collect();
+        }
+        (entries, label)
+    }
+}
+
+/// The configured entries of a menu followed by the entries of its module
+fn top<'a>(configured: &'a [MenuEntry], added: &'a [MenuEntry]) -> Vec<&'a MenuEntry> {
+    let separator = (!configured.is_empty() && !added.is_empty()).then_some(&SEPARATOR);
+    configured.iter().chain(separator).chain(added).collect()
+}
+
+/// The size of a menu, which is estimated from the font size. The popup is large enough for its
+/// largest submenu.
+pub fn menu_size(
+    configured: &[MenuEntry],
+    added: &[MenuEntry],
+    config: &PopupConfig,
+) -> (i32, i32) {
+    fn size(entries: &[&MenuEntry], config: &PopupConfig, submenu: bool) -> (f32, f32) {
+        let chars = entries
+            .iter()
+            .map(|entry| entry.label().chars().count())
+            .max()
+            .unwrap_or_default();
+        // Submenus have an entry which leads back to the parent menu
+        let back = match submenu {
+            true => SEPARATOR.height(config) + entry_height(config),
+            false => 0.,
+        };
+        let height = entries
+            .iter()
+            .map(|entry| entry.height(config))
+            .sum::<f32>()
+            + back;
+        let (width, height) = entries
+            .iter()
+            .filter_map(|entry| match entry {
+                MenuEntry::Submenu { entries, .. } => {
+                    Some(size(&entries.iter().collect::<Vec<_>>(), config, true))
+                }
+                _ => None,
+            })
+            .fold(
+                (chars as f32 * config.font_size * 0.6, height),
+                |(width, height), (w, h)| (width.max(w), height.max(h)),
+            );
+        (width, height)
+    }
+    let (width, height) = size(&top(configured, added), config, false);
+    // Leave space for the padding and the arrows of submenus
+    let width = width + config.font_size * 2. + 2. * ENTRY_PADDING[1] as f32;
+    (
+        (width + config.padding.horizontal()).ceil() as i32,
+        (height + config.padding.vertical()).ceil() as i32,
+    )
+}
+
+/// The view of an open menu. `id` is the id of its popup, `module` and `vars` are passed to the
+/// actions of its entries.
+pub fn view<'a>(
+    menu: &'a OpenMenu,
+    configured: &'a [MenuEntry],
+    id: Id,
+    module: TypeId,
+    vars: BTreeMap<&'static str, String>,
+    config: &'a PopupConfig,
+    anchor: &BarAnchor,
+) -> Element<'a, Message> {
+    let (entries, submenu) = menu.current(configured);
+    let (font_size, text_color) = (config.font_size, config.text_color);
+    let label = move |label: &'a str| text(label).size(font_size).color(text_color);
+    let entry = move |content: Element<'a, Message>| {
+        button(content)
+            .width(Fill)
+            .padding(ENTRY_PADDING)
+            .style(move |_, status| ButtonStyle {
+                background: matches!(status, Status::Hovered | Status::Pressed)
+                    .then(|| Background::Color(text_color.scale_alpha(0.2))),
+                text_color,
+                border: Border {
+                    radius: config.border.radius,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+    };
+    let separator = || -> Element<'a, Message> {
+        container(container(Space::new(Fill, 1.)).style(move |_| Style {
+            background: Some(Background::Color(text_color.scale_alpha(0.3))),
+            ..Default::default()
+        }))
+        .padding([SEPARATOR_MARGIN, 0])
+        .into()
+    };
+
+    let back = submenu.map(|submenu| {
+        [
+            entry(
+                row![label("‹"), label(submenu)]
+                    .spacing(font_size / 2.)
+                    .into(),
+            )
+            .on_event_try(navigate(id, None))
+            .into(),
+            separator(),
+        ]
+    });
+    let entries = entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, menu_entry)| match menu_entry {
+            MenuEntry::Item {
+                label: item,
+                action,
+            } => {
+                let vars = vars.clone();
+                entry(label(item).into())
+                    .on_event_try(move |event, layout, _, _, _| {
+                        event.is_left_click().then(|| {
+                            let ctx = ActionContext {
+                                module,
+                                bounds: layout.bounds(),
+                                vars: vars.clone(),
+                            };
+                            // Close the menu once an entry was chosen
+                            Message::Batch(vec![
+                                action.as_message(&ctx),
+                                Message::PopupEvent(id, PopupEvent::Close),
+                            ])
+                        })
+                    })
+                    .into()
+            }
+            MenuEntry::Separator => separator(),
+            MenuEntry::Submenu { label: submenu, .. } => {
+                entry(row![label(submenu), horizontal_space(), label("›")].into())
+                    .on_event_try(navigate(id, Some(index)))
+                    .into()
+            }
+        });
+
+    let menu = container(column(back.into_iter().flatten().chain(entries)))
+        .width(Fill)
+        .padding(config.padding)
+        .style(move |_| Style {
+            background: Some(config.background),
+            border: config.border,
+            ..Default::default()
+        });
+    align_popup(menu, anchor)
+}
+
+/// Opens a submenu of the menu with the given id on click, or goes back to the parent menu
+fn navigate(
+    id: Id,
+    submenu: Option<usize>,
+) -> impl Fn(ButtonEvent, Layout, mouse::Cursor, &mut dyn Clipboard, &Rectangle) -> Option<Message>
+{
+    move |event: ButtonEvent, _: Layout, _: mouse::Cursor, _: &mut dyn Clipboard, _: &Rectangle| {
+        event
+            .is_left_click()
+            .then_some(Message::PopupEvent(id, PopupEvent::Submenu(submenu)))
+    }
+}
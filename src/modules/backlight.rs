@@ -0,0 +1,369 @@
+use std::{
+    any::Any,
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use bar_rs_derive::Builder;
+use handlebars::Handlebars;
+use iced::widget::container;
+use iced::{futures::SinkExt, stream, widget::text, Element, Subscription};
+use tokio::{
+    fs,
+    io::unix::AsyncFd,
+    runtime, select,
+    sync::{broadcast, mpsc},
+    time::sleep,
+};
+
+use crate::config::popup_config::PopupConfig;
+use crate::{
+    config::{
+        anchor::BarAnchor,
+        module_config::{LocalModuleConfig, ModuleConfigOverride},
+    },
+    fill::FillExt,
+    helpers::{Formats, Thresholds},
+    Message, NERD_FONT,
+};
+use crate::{impl_on_click, impl_wrapper};
+
+use super::{Action, ActionContext, Module, OnClickAction};
+
+const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+
+#[derive(Debug, Builder)]
+pub struct BacklightMod {
+    devices: BTreeMap<String, Brightness>,
+    /// The device to show, the first one if None
+    device: Option<String>,
+    cfg_override: ModuleConfigOverride,
+    icons: BTreeMap<u8, String>,
+    default_action: OnClickAction,
+    formats: Formats,
+    thresholds: Thresholds,
+    sender: broadcast::Sender<Arc<dyn Any + Send + Sync>>,
+}
+
+impl Default for BacklightMod {
+    fn default() -> Self {
+        Self {
+            devices: BTreeMap::new(),
+            device: None,
+            cfg_override: Default::default(),
+            icons: BTreeMap::from([
+                (67, "󰃠".to_string()),
+                (34, "󰃟".to_string()),
+                (0, "󰃞".to_string()),
+            ]),
+            default_action: default_action(5),
+            formats: Formats::new("backlight"),
+            thresholds: Thresholds::new("brightness"),
+            sender: broadcast::channel(16).0,
+        }
+    }
+}
+
+/// Scrolling changes the brightness by `step` percent
+fn default_action(step: i16) -> OnClickAction {
+    OnClickAction {
+        scroll_up: Some(Box::new(BacklightStep(step))),
+        scroll_down: Some(Box::new(BacklightStep(-step))),
+        ..Default::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Brightness {
+    current: u32,
+    max: u32,
+}
+
+impl Brightness {
+    fn perc(&self) -> u8 {
+        match self.max {
+            0 => 0,
+            max => (self.current as f32 / max as f32 * 100.).round() as u8,
+        }
+    }
+}
+
+impl BacklightMod {
+    fn active_device(&self) -> Option<(&String, &Brightness)> {
+        match &self.device {
+            Some(name) => self.devices.get_key_value(name),
+            None => self.devices.iter().next(),
+        }
+    }
+
+    fn icon(&self, perc: u8) -> &String {
+        self.icons
+            .iter()
+            .filter(|(k, _)| perc >= **k)
+            .next_back()
+            .unwrap()
+            .1
+    }
+}
+
+/// Change the brightness of the active device by the given percentage
+#[derive(Debug)]
+struct BacklightStep(i16);
+
+impl Action for BacklightStep {
+    fn as_message(&self, _ctx: &ActionContext) -> Message {
+        let step = self.0;
+        Message::action(move |reg| {
+            let module = reg.get_module::<BacklightMod>();
+            if let Some((name, _)) = module.active_device() {
+                let request = StepRequest {
+                    device: name.clone(),
+                    step,
+                };
+                module.sender.send(Arc::new(request)).ok();
+            }
+        })
+    }
+}
+
+/// A brightness change, which is written by the subscription of [BacklightMod]
+#[derive(Debug)]
+struct StepRequest {
+    device: String,
+    step: i16,
+}
+
+/// Write the changed brightness of a device
+async fn step_brightness(devices: &mut BTreeMap<String, Brightness>, request: &StepRequest) {
+    let (name, step) = (&request.device, request.step);
+    let Some(brightness) = devices.get_mut(name) else {
+        return;
+    };
+    let perc = (brightness.perc() as i16 + step).clamp(1, 100) as u32;
+    let mut value = (perc * brightness.max + 50) / 100;
+    // Devices with few brightness levels would not change at all otherwise
+    if value == brightness.current {
+        value = match step.is_positive() {
+            true => value + 1,
+            false => value.saturating_sub(1),
+        };
+    }
+    // Never turn the backlight off completely
+    let value = value.min(brightness.max).max(1);
+    match fs::write(
+        PathBuf::from(BACKLIGHT_DIR).join(name).join("brightness"),
+        value.to_string(),
+    )
+    .await
+    {
+        // Remember the new value right away, so that fast scrolling adds up
+        Ok(()) => brightness.current = value,
+        Err(e) => eprintln!(
+            "Failed to set the brightness of {name}: {e}\n\
+            Make sure you are allowed to write to {BACKLIGHT_DIR}/{name}/brightness \
+            (e.g. by being in the video group)"
+        ),
+    }
+}
+
+impl Module for BacklightMod {
+    fn name(&self) -> String {
+        "backlight".to_string()
+    }
+
+    fn active(&self) -> bool {
+        self.active_device().is_some()
+    }
+
+    fn view(
+        &self,
+        config: &LocalModuleConfig,
+        _popup_config: &PopupConfig,
+        anchor: &BarAnchor,
+        handlebars: &Handlebars,
+    ) -> Element<'_, Message> {
+        let style = self.thresholds.style(&self.context(None));
+        let perc = self
+            .active_device()
+            .map(|(_, brightness)| brightness.perc())
+            .unwrap_or_default();
+        list![
+            anchor,
+            container(
+                text(self.icon(perc))
+                    .fill(anchor)
+                    .size(self.cfg_override.icon_size.unwrap_or(config.icon_size))
+                    .color(
+                        style
+                            .icon_color
+                            .or(self.cfg_override.icon_color)
+                            .unwrap_or(config.icon_color)
+                    )
+                    .font(NERD_FONT)
+            )
+            .padding(self.cfg_override.icon_margin.unwrap_or(config.icon_margin)),
+            container(
+                text(self.formats.render(handlebars, &self.context(None)))
+                    .fill(anchor)
+                    .size(self.cfg_override.font_size.unwrap_or(config.font_size))
+                    .color(
+                        style
+                            .text_color
+                            .or(self.cfg_override.text_color)
+                            .unwrap_or(config.text_color)
+                    )
+            )
+            .padding(self.cfg_override.text_margin.unwrap_or(config.text_margin)),
+        ]
+        .spacing(self.cfg_override.spacing.unwrap_or(config.spacing))
+        .into()
+    }
+
+    impl_wrapper!(thresholds);
+
+    fn read_config(
+        &mut self,
+        config: &HashMap<String, Option<String>>,
+        _popup_config: &HashMap<String, Option<String>>,
+        templates: &mut Handlebars,
+    ) {
+        self.cfg_override = config.into();
+        self.thresholds.read_config(config);
+        self.formats
+            .read_config(config, "{{brightness}}%", templates);
+        self.device = config.get("device").and_then(|v| v.clone());
+        self.default_action = default_action(
+            config
+                .get("step")
+                .and_then(|v| v.as_ref().and_then(|v| v.parse().ok()))
+                .unwrap_or(5),
+        );
+    }
+
+    impl_on_click!(default_action, formats);
+
+    fn next_format(&mut self) {
+        self.formats.next();
+    }
+
+    fn context(&self, _item: Option<usize>) -> BTreeMap<&'static str, String> {
+        let mut ctx: BTreeMap<&'static str, String> = self
+            .active_device()
+            .map(|(name, brightness)| {
+                BTreeMap::from([
+                    ("device", name.clone()),
+                    ("brightness", brightness.perc().to_string()),
+                ])
+            })
+            .unwrap_or_default();
+        ctx.insert("state", self.thresholds.state(&ctx).to_string());
+        ctx
+    }
+
+    fn subscription(&self) -> Option<iced::Subscription<Message>> {
+        Some(Subscription::run(|| {
+            let (changed_sx, mut changed) = mpsc::channel(10);
+            // udev devices can't be sent between threads
+            std::thread::spawn(move || {
+                let runtime = runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+
+                runtime.block_on(async move {
+                    let Ok(socket) = udev::MonitorBuilder::new()
+                        .and_then(|b| b.match_subsystem("backlight"))
+                        .and_then(|b| b.listen())
+                        .and_then(AsyncFd::new)
+                        .map_err(|e| eprintln!("Failed to monitor backlight devices: {e}"))
+                    else {
+                        return;
+                    };
+
+                    loop {
+                        let Ok(mut guard) = socket.readable().await else {
+                            return;
+                        };
+                        let changed = guard.get_inner().iter().count() > 0;
+                        guard.clear_ready();
+                        if changed && changed_sx.send(()).await.is_err() {
+                            return;
+                        }
+                    }
+                });
+            });
+
+            stream::channel(1, |mut sender| async move {
+                let (sx, mut rx) = mpsc::channel(1);
+                sender
+                    .send(Message::GetReceiver(sx, |reg| {
+                        reg.get_module::<BacklightMod>().sender.subscribe()
+                    }))
+                    .await
+                    .unwrap_or_else(|e| {
+                        eprintln!("Failed to request a receiver for backlight actions: {e}")
+                    });
+                let Some(mut requests) = rx.recv().await else {
+                    return;
+                };
+                drop(rx);
+
+                let mut devices = read_devices().await;
+                loop {
+                    let shown = devices.clone();
+                    if sender
+                        .send(Message::update(move |reg| {
+                            reg.get_module_mut::<BacklightMod>().devices = shown
+                        }))
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                    // Not every driver reports changes, so check every now and then
+                    select! {
+                        _ = sleep(Duration::from_secs(30)) => devices = read_devices().await,
+                        _ = changed.recv() => devices = read_devices().await,
+                        Ok(request) = requests.recv() => {
+                            if let Some(request) = request.downcast_ref::<StepRequest>() {
+                                step_brightness(&mut devices, request).await;
+                            }
+                        }
+                    }
+                }
+            })
+        }))
+    }
+}
+
+async fn read_devices() -> BTreeMap<String, Brightness> {
+    let mut devices = BTreeMap::new();
+    let Ok(mut entries) = fs::read_dir(BACKLIGHT_DIR).await else {
+        return devices;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let read = |file: &'static str| {
+            let path = entry.path().join(file);
+            async move {
+                fs::read_to_string(path)
+                    .await
+                    .ok()
+                    .and_then(|v| v.trim().parse().ok())
+            }
+        };
+        // `actual_brightness` is what the hardware reports, `brightness` what was requested
+        let (Some(current), Some(max)) = (
+            read("actual_brightness").await,
+            read("max_brightness").await,
+        ) else {
+            continue;
+        };
+        devices.insert(
+            entry.file_name().to_string_lossy().to_string(),
+            Brightness { current, max },
+        );
+    }
+    devices
+}
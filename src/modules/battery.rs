@@ -6,13 +6,13 @@ use bar_rs_derive::Builder;
 use handlebars::Handlebars;
 use iced::widget::button::Style;
 use iced::widget::{column, container, scrollable};
-use iced::{futures::SinkExt, stream, widget::text, Element, Subscription};
+use iced::{futures::SinkExt, mouse, stream, widget::text, Element, Subscription};
 use tokio::{fs, io, runtime, select, sync::mpsc, task, time::sleep};
 use udev::Device;
 
 use crate::button::button;
 use crate::config::popup_config::{PopupConfig, PopupConfigOverride};
-use crate::helpers::UnEscapeString;
+use crate::helpers::{Formats, Thresholds, UnEscapeString};
 use crate::{
     config::{
         anchor::BarAnchor,
@@ -21,7 +21,7 @@ use crate::{
     fill::FillExt,
     Message, NERD_FONT,
 };
-use crate::{impl_on_click, impl_wrapper};
+use crate::{impl_on_click, impl_popup_size, impl_wrapper};
 
 use super::Module;
 
@@ -33,6 +33,8 @@ pub struct BatteryMod {
     popup_cfg_override: PopupConfigOverride,
     icons: BTreeMap<u8, String>,
     icons_charging: BTreeMap<u8, String>,
+    formats: Formats,
+    thresholds: Thresholds,
 }
 
 impl Default for BatteryMod {
@@ -58,6 +60,8 @@ impl Default for BatteryMod {
                 (25, "󱊤 ".to_string()),
                 (0, "󰢟 ".to_string()),
             ]),
+            formats: Formats::new("battery").switch_on(mouse::Button::Right),
+            thresholds: Thresholds::descending("capacity"),
         }
     }
 }
@@ -83,6 +87,7 @@ impl BatteryMod {
 struct AverageStats {
     capacity: u8,
     charging: bool,
+    discharging: bool,
     hours: u16,
     minutes: u16,
     // If all batteries report a `power_now` value of 0 the remaining time can't be calculated
@@ -147,6 +152,7 @@ struct BatteryStats {
     power_now: f32,
     voltage_now: f32,
     charging: bool,
+    discharging: bool,
 }
 
 impl Module for BatteryMod {
@@ -161,6 +167,7 @@ impl Module for BatteryMod {
         anchor: &BarAnchor,
         handlebars: &Handlebars,
     ) -> Element<'_, Message> {
+        let style = self.thresholds.style(&self.context(None));
         let time_remaining = if self.avg.valid {
             let time_ctx =
                 BTreeMap::from([("hours", self.avg.hours), ("minutes", self.avg.minutes)]);
@@ -185,32 +192,36 @@ impl Module for BatteryMod {
                 container(
                     text(self.icon(None, None))
                         .fill(anchor)
-                        .color(self.cfg_override.icon_color.unwrap_or(config.icon_color))
+                        .color(
+                            style
+                                .icon_color
+                                .or(self.cfg_override.icon_color)
+                                .unwrap_or(config.icon_color)
+                        )
                         .size(self.cfg_override.icon_size.unwrap_or(config.icon_size))
                         .font(NERD_FONT)
                 )
                 .padding(self.cfg_override.icon_margin.unwrap_or(config.icon_margin)),
                 container(
-                    text(
-                        handlebars
-                            .render("battery", &ctx)
-                            .inspect_err(|e| eprintln!("Failed to render battery: {e}"))
-                            .unwrap_or_default()
-                    )
-                    .fill(anchor)
-                    .color(self.cfg_override.text_color.unwrap_or(config.text_color))
-                    .size(self.cfg_override.font_size.unwrap_or(config.font_size))
+                    text(self.formats.render(handlebars, &ctx))
+                        .fill(anchor)
+                        .color(
+                            style
+                                .text_color
+                                .or(self.cfg_override.text_color)
+                                .unwrap_or(config.text_color)
+                        )
+                        .size(self.cfg_override.font_size.unwrap_or(config.font_size))
                 )
                 .padding(self.cfg_override.text_margin.unwrap_or(config.text_margin)),
             ]
             .spacing(self.cfg_override.spacing.unwrap_or(config.spacing)),
         )
-        .on_event_with(Message::popup::<Self>(
+        .on_event_try(Message::popup::<Self>(
             self.popup_cfg_override.width.unwrap_or(popup_config.width),
             self.popup_cfg_override
                 .height
                 .unwrap_or(popup_config.height),
-            anchor,
         ))
         .style(|_, _| Style::default())
         .into()
@@ -296,7 +307,7 @@ impl Module for BatteryMod {
         .into()
     }
 
-    impl_wrapper!();
+    impl_wrapper!(thresholds);
 
     fn read_config(
         &mut self,
@@ -305,16 +316,10 @@ impl Module for BatteryMod {
         templates: &mut Handlebars,
     ) {
         self.cfg_override = config.into();
+        self.thresholds.read_config(config);
         self.popup_cfg_override.update(popup_config);
-        templates
-            .register_template_string(
-                "battery",
-                config
-                    .get("format")
-                    .unescape()
-                    .unwrap_or("{{capacity}}%{{time_remaining}}".to_string()),
-            )
-            .unwrap_or_else(|e| eprintln!("Failed to parse battery format: {e}"));
+        self.formats
+            .read_config(config, "{{capacity}}%{{time_remaining}}", templates);
         templates
             .register_template_string(
                 "battery_time_remaining",
@@ -343,7 +348,25 @@ impl Module for BatteryMod {
             .unwrap_or_else(|e| eprintln!("Failed to parse battery popup time format: {e}"));
     }
 
-    impl_on_click!();
+    impl_on_click!(formats);
+
+    fn next_format(&mut self) {
+        self.formats.next();
+    }
+
+    impl_popup_size!();
+
+    fn context(&self, _item: Option<usize>) -> BTreeMap<&'static str, String> {
+        let mut ctx = BTreeMap::from([
+            ("capacity", self.avg.capacity.to_string()),
+            ("charging", self.avg.charging.to_string()),
+            ("discharging", self.avg.discharging.to_string()),
+            ("hours", self.avg.hours.to_string()),
+            ("minutes", self.avg.minutes.to_string()),
+        ]);
+        ctx.insert("state", self.thresholds.state(&ctx).to_string());
+        ctx
+    }
 
     fn subscription(&self) -> Option<iced::Subscription<Message>> {
         Some(Subscription::run(|| {
@@ -429,6 +452,7 @@ impl From<(&Device, String)> for BatteryStats {
                 .parse()
                 .unwrap_or(0.),
             charging: matches!(get_property(device, "POWER_SUPPLY_STATUS"), "Charging"),
+            discharging: matches!(get_property(device, "POWER_SUPPLY_STATUS"), "Discharging"),
         }
     }
 }
@@ -455,6 +479,8 @@ impl From<&Vec<BatteryStats>> for AverageStats {
 
         let capacity = (100. / energy_full * energy_now).round() as u8;
         let charging = batteries.iter().any(|bat| bat.charging);
+        // Batteries which are neither charging nor discharging are full or held at a charge limit
+        let discharging = batteries.iter().any(|bat| bat.discharging);
         let time_remaining = match charging {
             true => {
                 (energy_full - energy_now)
@@ -467,6 +493,7 @@ impl From<&Vec<BatteryStats>> for AverageStats {
         AverageStats {
             capacity,
             charging,
+            discharging,
             hours: time_remaining.floor() as u16,
             minutes: ((time_remaining - time_remaining.floor()) * 60.) as u16,
             valid: power_now.is_normal(),
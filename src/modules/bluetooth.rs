@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     time::Duration,
 };
 
@@ -13,6 +13,8 @@ use tokio::{io, time::sleep};
 
 use crate::button::button;
 use crate::config::popup_config::PopupConfig;
+use crate::helpers::Formats;
+use crate::menu::{MenuEntry, MenuOnRightClick};
 use crate::{
     config::{
         anchor::BarAnchor,
@@ -29,17 +31,24 @@ use super::Module;
 struct Device {
     icon: &'static str,
     name: String,
+    address: String,
 }
 
 #[derive(Clone, Debug)]
 struct Controller {
     is_powered: bool,
     connected_devices: HashSet<Device>,
+    /// Paired devices which aren't connected right now
+    paired_devices: HashSet<Device>,
 }
 
 impl Controller {
-    async fn get_all_devices(adapter: &Adapter) -> Result<HashSet<Device>, io::Error> {
+    /// The connected and the paired devices of an adapter
+    async fn get_all_devices(
+        adapter: &Adapter,
+    ) -> Result<(HashSet<Device>, HashSet<Device>), io::Error> {
         let mut connected_devices = HashSet::new();
+        let mut paired_devices = HashSet::new();
 
         let connected_devices_addresses = adapter.device_addresses().await?;
         for addr in connected_devices_addresses {
@@ -59,33 +68,52 @@ impl Controller {
                 "None" => "",
                 _ => "",
             };
-            if device.is_connected().await? {
-                connected_devices.insert(Device {
-                    icon,
-                    name: device.alias().await?,
-                });
-            }
+            let devices = match device.is_connected().await? {
+                true => &mut connected_devices,
+                false if device.is_paired().await? => &mut paired_devices,
+                false => continue,
+            };
+            devices.insert(Device {
+                icon,
+                name: device.alias().await?,
+                address: addr.to_string(),
+            });
         }
-        Ok(connected_devices)
+        Ok((connected_devices, paired_devices))
     }
 
     async fn from_adaper(adapter: Adapter) -> Result<Controller, io::Error> {
         let is_powered = adapter.is_powered().await?;
+        let (connected_devices, paired_devices) = if is_powered {
+            Controller::get_all_devices(&adapter).await?
+        } else {
+            Default::default()
+        };
         Ok(Controller {
             is_powered,
-            connected_devices: if is_powered {
-                Controller::get_all_devices(&adapter).await?
-            } else {
-                HashSet::default()
-            },
+            connected_devices,
+            paired_devices,
         })
     }
 }
 
-#[derive(Default, Debug, Builder)]
+#[derive(Debug, Builder)]
 pub struct BluetoothMod {
     controllers: Vec<Controller>,
     cfg_override: ModuleConfigOverride,
+    menu: MenuOnRightClick,
+    formats: Formats,
+}
+
+impl Default for BluetoothMod {
+    fn default() -> Self {
+        Self {
+            controllers: vec![],
+            cfg_override: Default::default(),
+            menu: MenuOnRightClick,
+            formats: Formats::new("bluetooth"),
+        }
+    }
 }
 
 impl BluetoothMod {
@@ -115,28 +143,23 @@ impl Module for BluetoothMod {
         config: &LocalModuleConfig,
         _popup_config: &PopupConfig,
         anchor: &BarAnchor,
-        _handlebars: &Handlebars,
+        handlebars: &Handlebars,
     ) -> Element<'_, Message> {
         let connected_devices = self.connected_devices();
-        let (bt_icons, bt_text) = match connected_devices.len() {
-            0 => (self.status_icon().to_string(), None),
-            // Show name if only one connected device
-            1 => {
-                let device = connected_devices.iter().next().unwrap();
-                (device.icon.to_string(), Some(&device.name))
-            }
+        let bt_icons = match connected_devices.len() {
+            0 => self.status_icon().to_string(),
+            1 => connected_devices.iter().next().unwrap().icon.to_string(),
             // Show icons for connected Bluetooth devices
-            _ => (
-                connected_devices
-                    .iter()
-                    .fold(String::new(), |mut acc, elem| {
-                        acc.push_str(elem.icon);
-                        acc
-                    }),
-                None,
-            ),
+            _ => connected_devices
+                .iter()
+                .fold(String::new(), |mut acc, elem| {
+                    acc.push_str(elem.icon);
+                    acc
+                }),
         };
-        let list = if let Some(bt_text) = bt_text {
+        // By default, the name is shown if only one device is connected
+        let bt_text = self.formats.render(handlebars, &self.context(None));
+        let list = if !bt_text.is_empty() {
             list![
                 anchor,
                 container(
@@ -180,12 +203,82 @@ impl Module for BluetoothMod {
         &mut self,
         config: &HashMap<String, Option<String>>,
         _popup_config: &HashMap<String, Option<String>>,
-        _templates: &mut Handlebars,
+        templates: &mut Handlebars,
     ) {
         self.cfg_override = config.into();
+        self.formats.read_config(
+            config,
+            r#"{{#if (eq connected "1")}}{{devices}}{{/if}}"#,
+            templates,
+        );
     }
 
-    impl_on_click!();
+    impl_on_click!(formats, menu);
+
+    fn next_format(&mut self) {
+        self.formats.next();
+    }
+
+    fn menu(&self) -> Vec<MenuEntry> {
+        let powered = self.controllers.iter().any(|c| c.is_powered);
+        let mut entries = vec![match powered {
+            true => MenuEntry::command("Power off", "bluetoothctl power off"),
+            false => MenuEntry::command("Power on", "bluetoothctl power on"),
+        }];
+        let mut connected = self.connected_devices().into_iter().collect::<Vec<_>>();
+        connected.sort_by(|a, b| a.name.cmp(&b.name));
+        entries.extend(connected.into_iter().map(|device| {
+            MenuEntry::command(
+                format!("Disconnect {}", device.name),
+                format!("bluetoothctl disconnect {}", device.address),
+            )
+        }));
+        let mut paired = self
+            .controllers
+            .iter()
+            .flat_map(|c| &c.paired_devices)
+            .collect::<Vec<_>>();
+        paired.sort_by(|a, b| a.name.cmp(&b.name));
+        if !paired.is_empty() {
+            entries.push(MenuEntry::Submenu {
+                label: "Connect".to_string(),
+                entries: paired
+                    .into_iter()
+                    .map(|device| {
+                        MenuEntry::command(
+                            format!("{} {}", device.icon, device.name),
+                            format!("bluetoothctl connect {}", device.address),
+                        )
+                    })
+                    .collect(),
+            });
+        }
+        entries
+    }
+
+    fn context(&self, _item: Option<usize>) -> BTreeMap<&'static str, String> {
+        let mut devices: Vec<&String> = self
+            .connected_devices()
+            .into_iter()
+            .map(|device| &device.name)
+            .collect();
+        devices.sort();
+        BTreeMap::from([
+            (
+                "powered",
+                self.controllers.iter().any(|c| c.is_powered).to_string(),
+            ),
+            ("connected", devices.len().to_string()),
+            (
+                "devices",
+                devices
+                    .into_iter()
+                    .map(String::as_str)
+                    .collect::<Vec<&str>>()
+                    .join(", "),
+            ),
+        ])
+    }
 
     fn subscription(&self) -> Option<iced::Subscription<Message>> {
         Some(Subscription::run(|| {
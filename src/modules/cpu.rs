@@ -10,7 +10,7 @@ use std::{
 use bar_rs_derive::Builder;
 use handlebars::Handlebars;
 use iced::widget::{button::Style, container, scrollable, Container, Text};
-use iced::{futures::SinkExt, stream, widget::text, Element, Subscription};
+use iced::{futures::SinkExt, mouse, stream, widget::text, Element, Subscription};
 use tokio::time::sleep;
 
 use crate::{
@@ -21,8 +21,8 @@ use crate::{
         popup_config::{PopupConfig, PopupConfigOverride},
     },
     fill::FillExt,
-    helpers::UnEscapeString,
-    impl_on_click, impl_wrapper, Message, NERD_FONT,
+    helpers::{Formats, Thresholds, UnEscapeString},
+    impl_on_click, impl_popup_size, impl_wrapper, Message, NERD_FONT,
 };
 
 use super::Module;
@@ -34,6 +34,8 @@ pub struct CpuMod {
     cfg_override: ModuleConfigOverride,
     popup_cfg_override: PopupConfigOverride,
     icon: Option<String>,
+    formats: Formats,
+    thresholds: Thresholds,
 }
 
 impl Default for CpuMod {
@@ -48,6 +50,8 @@ impl Default for CpuMod {
                 ..Default::default()
             },
             icon: None,
+            formats: Formats::new("cpu").switch_on(mouse::Button::Right),
+            thresholds: Thresholds::new("total"),
         }
     }
 }
@@ -62,8 +66,9 @@ impl Module for CpuMod {
         config: &LocalModuleConfig,
         popup_config: &PopupConfig,
         anchor: &BarAnchor,
-        _handlebars: &Handlebars,
+        handlebars: &Handlebars,
     ) -> Element<'_, Message> {
+        let style = self.thresholds.style(&self.context(None));
         button(
             list![
                 anchor,
@@ -71,26 +76,35 @@ impl Module for CpuMod {
                     text!("{}", self.icon.as_ref().unwrap_or(&"󰻠".to_string()))
                         .fill(anchor)
                         .size(self.cfg_override.icon_size.unwrap_or(config.icon_size))
-                        .color(self.cfg_override.icon_color.unwrap_or(config.icon_color))
+                        .color(
+                            style
+                                .icon_color
+                                .or(self.cfg_override.icon_color)
+                                .unwrap_or(config.icon_color)
+                        )
                         .font(NERD_FONT)
                 )
                 .padding(self.cfg_override.icon_margin.unwrap_or(config.icon_margin)),
                 container(
-                    text!["{}%", self.avg_usage.all]
+                    text(self.formats.render(handlebars, &self.context(None)))
                         .fill(anchor)
                         .size(self.cfg_override.font_size.unwrap_or(config.font_size))
-                        .color(self.cfg_override.text_color.unwrap_or(config.text_color))
+                        .color(
+                            style
+                                .text_color
+                                .or(self.cfg_override.text_color)
+                                .unwrap_or(config.text_color)
+                        )
                 )
                 .padding(self.cfg_override.text_margin.unwrap_or(config.text_margin)),
             ]
             .spacing(self.cfg_override.spacing.unwrap_or(config.spacing)),
         )
-        .on_event_with(Message::popup::<Self>(
+        .on_event_try(Message::popup::<Self>(
             self.popup_cfg_override.width.unwrap_or(popup_config.width),
             self.popup_cfg_override
                 .height
                 .unwrap_or(popup_config.height),
-            anchor,
         ))
         .style(|_, _| Style::default())
         .into()
@@ -120,34 +134,29 @@ impl Module for CpuMod {
                     .unwrap_or(config.text_margin),
             )
         };
-        let ctx = BTreeMap::from([
-            ("total", self.avg_usage.all.to_string()),
-            ("user", self.avg_usage.user.to_string()),
-            ("system", self.avg_usage.system.to_string()),
-            ("guest", self.avg_usage.guest.to_string()),
-            (
-                "cores",
-                self.cores
-                    .iter()
-                    .map(|(ty, stats)| {
-                        let core = BTreeMap::from([
-                            ("index", ty.get_core_index().to_string()),
-                            ("total", stats.all.to_string()),
-                            ("user", stats.user.to_string()),
-                            ("system", stats.system.to_string()),
-                            ("guest", stats.guest.to_string()),
-                        ]);
-                        template
-                            .render("cpu_core", &core)
-                            .map_err(|e| eprintln!("Failed to render cpu core stats: {e}"))
-                            .unwrap_or_default()
-                    })
-                    .collect::<Vec<String>>()
-                    .join("\n"),
-            ),
-        ]);
+        let mut ctx = self.context(None);
+        ctx.insert(
+            "cores",
+            self.cores
+                .iter()
+                .map(|(ty, stats)| {
+                    let core = BTreeMap::from([
+                        ("index", ty.get_core_index().to_string()),
+                        ("total", stats.all.to_string()),
+                        ("user", stats.user.to_string()),
+                        ("system", stats.system.to_string()),
+                        ("guest", stats.guest.to_string()),
+                    ]);
+                    template
+                        .render("cpu_core", &core)
+                        .map_err(|e| eprintln!("Failed to render cpu core stats: {e}"))
+                        .unwrap_or_default()
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+        );
         let format = template
-            .render("cpu", &ctx)
+            .render("cpu_popup", &ctx)
             .map_err(|e| eprintln!("Failed to render cpu stats: {e}"))
             .unwrap_or_default();
         container(scrollable(fmt_text(text(format))))
@@ -169,7 +178,7 @@ impl Module for CpuMod {
             .into()
     }
 
-    impl_wrapper!();
+    impl_wrapper!(thresholds);
 
     fn read_config(
         &mut self,
@@ -178,11 +187,13 @@ impl Module for CpuMod {
         templates: &mut Handlebars,
     ) {
         self.cfg_override = config.into();
+        self.thresholds.read_config(config);
         self.popup_cfg_override.update(popup_config);
         self.icon = config.get("icon").and_then(|v| v.clone());
+        self.formats.read_config(config, "{{total}}%", templates);
         templates
             .register_template_string(
-                "cpu",
+                "cpu_popup",
                 popup_config
                     .get("format")
                     .unescape()
@@ -200,7 +211,32 @@ impl Module for CpuMod {
             .unwrap_or_else(|e| eprintln!("Failed to parse battery popup format: {e}"));
     }
 
-    impl_on_click!();
+    impl_on_click!(formats);
+
+    fn next_format(&mut self) {
+        self.formats.next();
+    }
+
+    impl_popup_size!();
+
+    fn context(&self, _item: Option<usize>) -> BTreeMap<&'static str, String> {
+        let mut ctx = BTreeMap::from([
+            ("total", self.avg_usage.all.to_string()),
+            ("user", self.avg_usage.user.to_string()),
+            ("system", self.avg_usage.system.to_string()),
+            ("guest", self.avg_usage.guest.to_string()),
+            (
+                "core_usage",
+                self.cores
+                    .values()
+                    .map(|stats| stats.all.to_string())
+                    .collect::<Vec<String>>()
+                    .join(","),
+            ),
+        ]);
+        ctx.insert("state", self.thresholds.state(&ctx).to_string());
+        ctx
+    }
 
     fn subscription(&self) -> Option<iced::Subscription<Message>> {
         Some(Subscription::run(|| {
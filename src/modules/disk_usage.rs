@@ -2,15 +2,19 @@ use std::{
     collections::{BTreeMap, HashMap},
     ffi::CString,
     mem,
+    time::Duration,
 };
 
 use bar_rs_derive::Builder;
 use handlebars::Handlebars;
 use iced::{
+    futures::SinkExt,
+    mouse, stream,
     widget::{button::Style, container, scrollable, text, Container, Text},
-    Element,
+    Element, Subscription,
 };
 use libc::{__errno_location, statvfs};
+use tokio::time::sleep;
 
 use crate::{
     button::button,
@@ -20,21 +24,42 @@ use crate::{
         popup_config::{PopupConfig, PopupConfigOverride},
     },
     fill::FillExt,
-    helpers::UnEscapeString,
-    impl_on_click, impl_wrapper, Message, NERD_FONT,
+    helpers::{Formats, Thresholds, UnEscapeString},
+    impl_on_click, impl_popup_size, impl_wrapper, Message, NERD_FONT,
 };
 
 use super::Module;
 
-#[derive(Debug, Builder, Default)]
+/// How often the statistics of the file system are read
+const INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Builder)]
 pub struct DiskUsageMod {
     icon: Option<String>,
     cfg_override: ModuleConfigOverride,
     popup_cfg_override: PopupConfigOverride,
     path: CString,
+    /// The last statistics of the file system, `None` if they couldn't be read
+    stats: Option<FileSystemStats>,
+    formats: Formats,
+    thresholds: Thresholds,
+}
+
+impl Default for DiskUsageMod {
+    fn default() -> Self {
+        Self {
+            icon: None,
+            cfg_override: Default::default(),
+            popup_cfg_override: Default::default(),
+            path: CString::default(),
+            stats: None,
+            formats: Formats::new("disk_usage").switch_on(mouse::Button::Right),
+            thresholds: Thresholds::new("used_perc"),
+        }
+    }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy)]
 /// All values are represented in megabytes, except the `_perc` fields
 struct FileSystemStats {
     total: u64,
@@ -86,14 +111,12 @@ impl Module for DiskUsageMod {
         anchor: &BarAnchor,
         handlebars: &Handlebars,
     ) -> Element<'_, Message> {
-        let Ok(stats) = get_stats(&self.path) else {
+        let style = self.thresholds.style(&self.context(None));
+        let Some(stats) = self.stats else {
             return "Error".into();
         };
         let ctx: BTreeMap<&'static str, u64> = stats.into();
-        let format = handlebars
-            .render("disk_usage", &ctx)
-            .map_err(|e| eprintln!("Failed to render disk_usage stats: {e}"))
-            .unwrap_or_default();
+        let format = self.formats.render(handlebars, &ctx);
         button(
             list![
                 anchor,
@@ -101,7 +124,12 @@ impl Module for DiskUsageMod {
                     text!("{}", self.icon.as_ref().unwrap_or(&"󰦚".to_string()))
                         .fill(anchor)
                         .size(self.cfg_override.icon_size.unwrap_or(config.icon_size))
-                        .color(self.cfg_override.icon_color.unwrap_or(config.icon_color))
+                        .color(
+                            style
+                                .icon_color
+                                .or(self.cfg_override.icon_color)
+                                .unwrap_or(config.icon_color)
+                        )
                         .font(NERD_FONT)
                 )
                 .padding(self.cfg_override.icon_margin.unwrap_or(config.icon_margin)),
@@ -109,18 +137,22 @@ impl Module for DiskUsageMod {
                     text(format)
                         .fill(anchor)
                         .size(self.cfg_override.font_size.unwrap_or(config.font_size))
-                        .color(self.cfg_override.text_color.unwrap_or(config.text_color))
+                        .color(
+                            style
+                                .text_color
+                                .or(self.cfg_override.text_color)
+                                .unwrap_or(config.text_color)
+                        )
                 )
                 .padding(self.cfg_override.text_margin.unwrap_or(config.text_margin)),
             ]
             .spacing(self.cfg_override.spacing.unwrap_or(config.spacing)),
         )
-        .on_event_with(Message::popup::<Self>(
+        .on_event_try(Message::popup::<Self>(
             self.popup_cfg_override.width.unwrap_or(popup_config.width),
             self.popup_cfg_override
                 .height
                 .unwrap_or(popup_config.height),
-            anchor,
         ))
         .style(|_, _| Style::default())
         .into()
@@ -150,7 +182,7 @@ impl Module for DiskUsageMod {
                     .unwrap_or(config.text_margin),
             )
         };
-        let Ok(stats) = get_stats(&self.path) else {
+        let Some(stats) = self.stats else {
             return "Error".into();
         };
         let ctx: BTreeMap<&'static str, u64> = stats.into();
@@ -177,7 +209,7 @@ impl Module for DiskUsageMod {
             .into()
     }
 
-    impl_wrapper!();
+    impl_wrapper!(thresholds);
 
     fn read_config(
         &mut self,
@@ -186,21 +218,16 @@ impl Module for DiskUsageMod {
         templates: &mut Handlebars,
     ) {
         self.cfg_override = config.into();
+        self.thresholds.read_config(config);
         self.popup_cfg_override.update(popup_config);
         self.icon = config.get("icon").and_then(|v| v.clone());
         self.path = config
             .get("path")
             .and_then(|v| v.clone().and_then(|v| CString::new(v).ok()))
             .unwrap_or_else(|| CString::new("/").unwrap());
-        templates
-            .register_template_string(
-                "disk_usage",
-                config
-                    .get("format")
-                    .unescape()
-                    .unwrap_or("{{used_perc}}%".to_string()),
-            )
-            .unwrap_or_else(|e| eprintln!("Failed to parse battery popup format: {e}"));
+        self.stats = get_stats(&self.path).ok();
+        self.formats
+            .read_config(config, "{{used_perc}}%", templates);
         templates
             .register_template_string(
                 "disk_usage_popup",
@@ -212,7 +239,46 @@ impl Module for DiskUsageMod {
             .unwrap_or_else(|e| eprintln!("Failed to parse battery popup format: {e}"));
     }
 
-    impl_on_click!();
+    impl_on_click!(formats);
+
+    fn next_format(&mut self) {
+        self.formats.next();
+    }
+
+    impl_popup_size!();
+
+    fn context(&self, _item: Option<usize>) -> BTreeMap<&'static str, String> {
+        let stats: BTreeMap<&'static str, u64> = self.stats.unwrap_or_default().into();
+        let mut ctx: BTreeMap<&'static str, String> = stats
+            .into_iter()
+            .map(|(key, value)| (key, value.to_string()))
+            .chain([("path", self.path.to_string_lossy().to_string())])
+            .collect();
+        ctx.insert("state", self.thresholds.state(&ctx).to_string());
+        ctx
+    }
+
+    fn subscription(&self) -> Option<iced::Subscription<Message>> {
+        let path = self.path.clone();
+        // A new subscription is started if the path changes
+        Some(Subscription::run_with_id(
+            path.clone(),
+            stream::channel(1, |mut sender| async move {
+                loop {
+                    let stats = get_stats(&path).ok();
+                    sender
+                        .send(Message::update(move |reg| {
+                            reg.get_module_mut::<DiskUsageMod>().stats = stats
+                        }))
+                        .await
+                        .unwrap_or_else(|err| {
+                            eprintln!("Trying to send disk usage stats failed with err: {err}");
+                        });
+                    sleep(INTERVAL).await;
+                }
+            }),
+        ))
+    }
 }
 
 /// Get file system statistics using the statvfs system call, see
@@ -1,14 +1,21 @@
-use std::{any::TypeId, collections::HashMap};
+use std::{
+    any::TypeId,
+    collections::{BTreeMap, HashMap},
+};
 
 use bar_rs_derive::Builder;
 use handlebars::Handlebars;
-use iced::widget::{container, rich_text, span, text};
+use hyprland::data::Client;
+use iced::widget::{container, text};
 use iced::{
     futures::{channel::mpsc::Sender, SinkExt},
     Element,
 };
 
 use crate::config::popup_config::PopupConfig;
+use crate::helpers::Formats;
+use crate::marquee::trim;
+use crate::rotate::bar_text;
 use crate::tooltip::ElementExt;
 use crate::{
     config::{
@@ -24,34 +31,24 @@ use crate::{impl_on_click, impl_wrapper};
 #[derive(Debug, Builder)]
 pub struct HyprWindowMod {
     title: Option<String>,
+    floating: bool,
     max_length: usize,
     cfg_override: ModuleConfigOverride,
+    formats: Formats,
 }
 
 impl Default for HyprWindowMod {
     fn default() -> Self {
         Self {
             title: None,
+            floating: false,
             max_length: 25,
             cfg_override: Default::default(),
+            formats: Formats::new("hyprland.window"),
         }
     }
 }
 
-impl HyprWindowMod {
-    pub fn get_title(&self) -> Option<String> {
-        self.title
-            .as_ref()
-            .map(|title| match title.len() > self.max_length {
-                true => format!(
-                    "{}...",
-                    title.chars().take(self.max_length - 3).collect::<String>()
-                ),
-                false => title.to_string(),
-            })
-    }
-}
-
 impl Module for HyprWindowMod {
     fn name(&self) -> String {
         "hyprland.window".to_string()
@@ -66,18 +63,27 @@ impl Module for HyprWindowMod {
         config: &LocalModuleConfig,
         _popup_config: &PopupConfig,
         anchor: &BarAnchor,
-        _handlebars: &Handlebars,
+        handlebars: &Handlebars,
     ) -> Element<'_, Message> {
-        container(
-            rich_text([span(self.get_title().unwrap_or_default())
-                .size(self.cfg_override.font_size.unwrap_or(config.font_size))
-                .color(self.cfg_override.text_color.unwrap_or(config.text_color))])
-            .fill(anchor),
-        )
+        let title = self.formats.render(handlebars, &self.context(None));
+        let marquee = self.cfg_override.marquee.unwrap_or(config.marquee);
+        container(bar_text(
+            match marquee.enabled {
+                true => title.clone(),
+                false => trim(&title, self.max_length),
+            },
+            self.cfg_override.font_size.unwrap_or(config.font_size),
+            self.cfg_override.text_color.unwrap_or(config.text_color),
+            anchor,
+            self.cfg_override.rotate_text.unwrap_or(config.rotate_text),
+            marquee,
+        ))
         .padding(self.cfg_override.text_margin.unwrap_or(config.text_margin))
         .tooltip_maybe(
-            self.get_title()
-                .and_then(|t| (t.len() > self.max_length).then_some(text(t).size(12))),
+            (title.chars().count() > self.max_length)
+                .then(|| text(title).size(config.tooltip.font_size)),
+            &config.tooltip,
+            anchor,
         )
     }
 
@@ -91,22 +97,36 @@ impl Module for HyprWindowMod {
         &mut self,
         config: &HashMap<String, Option<String>>,
         _popup_config: &HashMap<String, Option<String>>,
-        _templates: &mut Handlebars,
+        templates: &mut Handlebars,
     ) {
         self.cfg_override = config.into();
+        self.formats.read_config(config, "{{title}}", templates);
         self.max_length = config
             .get("max_length")
             .and_then(|v| v.as_ref().and_then(|v| v.parse().ok()))
             .unwrap_or(Self::default().max_length);
     }
 
-    impl_on_click!();
+    impl_on_click!(formats);
+
+    fn next_format(&mut self) {
+        self.formats.next();
+    }
+
+    fn context(&self, _item: Option<usize>) -> BTreeMap<&'static str, String> {
+        BTreeMap::from([
+            ("title", self.title.clone().unwrap_or_default()),
+            ("floating", self.floating.to_string()),
+        ])
+    }
 }
 
-pub async fn update_window(sender: &mut Sender<Message>, title: Option<String>) {
+pub async fn update_window(sender: &mut Sender<Message>, window: Option<Client>) {
     sender
         .send(Message::update(move |reg| {
-            reg.get_module_mut::<HyprWindowMod>().title = title
+            let window_mod = reg.get_module_mut::<HyprWindowMod>();
+            window_mod.floating = window.as_ref().is_some_and(|w| w.floating);
+            window_mod.title = window.map(|w| w.title);
         }))
         .await
         .unwrap_or_else(|err| {
@@ -1,37 +1,47 @@
-use std::{any::TypeId, collections::HashMap, time::Duration};
+use std::{
+    any::TypeId,
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
 
 use bar_rs_derive::Builder;
 use handlebars::Handlebars;
 use hyprland::{
     data::{Workspace, Workspaces},
+    dispatch::{Dispatch, DispatchType, WorkspaceIdentifierWithSpecial},
     shared::{HyprData, HyprDataActive, HyprDataVec},
 };
 use iced::{
-    widget::{container, rich_text, span},
-    Background, Border, Color, Element, Padding,
+    widget::{container, container::Style, text},
+    Background, Border, Color, Element,
+    Length::Fill,
+    Padding,
 };
 use tokio::time::sleep;
 
 use crate::{
+    animation::{Animated, Highlight},
     config::{
         anchor::BarAnchor,
+        animation_config::AnimationConfig,
         module_config::{LocalModuleConfig, ModuleConfigOverride},
         parse::StringExt,
         popup_config::PopupConfig,
     },
-    fill::FillExt,
     impl_on_click, impl_wrapper,
     list::list,
     listeners::hyprland::HyprListener,
-    modules::{require_listener, Module},
+    modules::{require_listener, Action, ActionContext, Module, OnClickAction},
     Message, NERD_FONT,
 };
 
 #[derive(Debug, Builder)]
 pub struct HyprWorkspaceMod {
     pub active: usize,
-    // (Name, Fullscreen state)
-    pub open: Vec<(String, bool)>,
+    // (Id, Name, Fullscreen state, Window count)
+    pub open: Vec<(i32, String, bool, u16)>,
+    /// The position of the highlight of the active workspace, which slides between workspaces
+    pub highlight: Animated<f32>,
     cfg_override: ModuleConfigOverride,
     icon_padding: Padding,
     icon_background: Option<Background>,
@@ -41,6 +51,7 @@ pub struct HyprWorkspaceMod {
     active_color: Color,
     active_background: Option<Background>,
     active_icon_border: Border,
+    default_action: OnClickAction,
 }
 
 impl Default for HyprWorkspaceMod {
@@ -48,6 +59,7 @@ impl Default for HyprWorkspaceMod {
         Self {
             active: 0,
             open: vec![],
+            highlight: Animated::default(),
             cfg_override: ModuleConfigOverride::default(),
             icon_padding: Padding::default(),
             icon_background: None,
@@ -57,10 +69,31 @@ impl Default for HyprWorkspaceMod {
             active_color: Color::WHITE,
             active_background: None,
             active_icon_border: Border::default().rounded(8),
+            default_action: OnClickAction {
+                scroll_up: Some(Box::new(FocusWorkspace(-1))),
+                scroll_down: Some(Box::new(FocusWorkspace(1))),
+                ..Default::default()
+            },
         }
     }
 }
 
+/// Focus the open workspace at the given offset from the active one
+#[derive(Debug)]
+struct FocusWorkspace(i32);
+
+impl Action for FocusWorkspace {
+    fn as_message(&self, _ctx: &ActionContext) -> Message {
+        let offset = self.0;
+        Message::action(move |_| {
+            Dispatch::call(DispatchType::Workspace(
+                WorkspaceIdentifierWithSpecial::RelativeOpen(offset),
+            ))
+            .unwrap_or_else(|e| eprintln!("[hyprland.workspaces] Failed to switch workspace: {e}"))
+        })
+    }
+}
+
 impl Module for HyprWorkspaceMod {
     fn name(&self) -> String {
         "hyprland.workspaces".to_string()
@@ -73,36 +106,64 @@ impl Module for HyprWorkspaceMod {
         anchor: &BarAnchor,
         _handlebars: &Handlebars,
     ) -> Element<'_, Message> {
-        list(
+        let workspaces = list(
             anchor,
-            self.open.iter().enumerate().map(|(id, (ws, _))| {
-                let mut span = span(ws)
-                    .padding(self.icon_padding)
-                    .size(self.cfg_override.icon_size.unwrap_or(config.icon_size))
-                    .color(self.cfg_override.icon_color.unwrap_or(config.icon_color))
-                    .background_maybe(self.icon_background)
-                    .border(self.icon_border)
-                    .font(NERD_FONT);
-                if id == self.active {
-                    span = span
-                        .padding(self.active_padding.unwrap_or(self.icon_padding))
-                        .size(self.active_size)
-                        .color(self.active_color)
-                        .background_maybe(self.active_background)
-                        .border(self.active_icon_border);
+            self.open.iter().enumerate().map(|(id, (_, ws, _, _))| {
+                let active = id == self.active;
+                let icon = container(
+                    text(ws)
+                        .size(match active {
+                            true => self.active_size,
+                            false => self.cfg_override.icon_size.unwrap_or(config.icon_size),
+                        })
+                        .color(match active {
+                            true => self.active_color,
+                            false => self.cfg_override.icon_color.unwrap_or(config.icon_color),
+                        })
+                        .font(NERD_FONT),
+                )
+                .padding(match active {
+                    true => self.active_padding.unwrap_or(self.icon_padding),
+                    false => self.icon_padding,
+                })
+                // The background of the active workspace is drawn by the highlight
+                .style(move |_| match active {
+                    true => Style::default(),
+                    false => Style {
+                        background: self.icon_background,
+                        border: self.icon_border,
+                        ..Default::default()
+                    },
+                });
+                match anchor.vertical() {
+                    true => container(icon).center_x(Fill),
+                    false => container(icon).center_y(Fill),
                 }
-                container(rich_text![span].fill(anchor))
-                    .padding(self.cfg_override.icon_margin.unwrap_or(config.icon_margin))
-                    .into()
+                .padding(self.cfg_override.icon_margin.unwrap_or(config.icon_margin))
+                .into()
             }),
         )
         .padding(self.cfg_override.padding.unwrap_or(config.padding))
-        .spacing(self.cfg_override.spacing.unwrap_or(config.spacing))
+        .spacing(self.cfg_override.spacing.unwrap_or(config.spacing));
+        Highlight::new(
+            workspaces,
+            self.highlight.value(),
+            self.active_background,
+            self.active_icon_border,
+        )
         .into()
     }
 
     impl_wrapper!();
 
+    fn set_animation(&mut self, config: &AnimationConfig) {
+        self.highlight.configure(config);
+    }
+
+    fn animating(&self) -> bool {
+        self.highlight.is_animating()
+    }
+
     fn requires(&self) -> Vec<TypeId> {
         vec![require_listener::<HyprListener>()]
     }
@@ -177,10 +238,27 @@ impl Module for HyprWorkspaceMod {
         };
     }
 
-    impl_on_click!();
+    impl_on_click!(default_action);
+
+    fn context(&self, item: Option<usize>) -> BTreeMap<&'static str, String> {
+        let mut ctx = BTreeMap::new();
+        if let Some((id, name, fullscreen, windows)) = self.open.get(self.active) {
+            ctx.extend([
+                ("active_id", id.to_string()),
+                ("active_name", name.clone()),
+                ("fullscreen", fullscreen.to_string()),
+                ("windows", windows.to_string()),
+            ]);
+        }
+        // The workspace under the cursor
+        if let Some((id, name, _, _)) = item.and_then(|i| self.open.get(i)) {
+            ctx.extend([("id", id.to_string()), ("name", name.clone())]);
+        }
+        ctx
+    }
 }
 
-pub async fn get_workspaces(active: Option<i32>) -> (usize, Vec<(String, bool)>) {
+pub async fn get_workspaces(active: Option<i32>) -> (usize, Vec<(i32, String, bool, u16)>) {
     // Sleep a bit, to reduce the probability that a non existing ws is still reported active
     sleep(Duration::from_millis(10)).await;
     let Ok(workspaces) = Workspaces::get_async().await else {
@@ -198,7 +276,7 @@ pub async fn get_workspaces(active: Option<i32>) -> (usize, Vec<(String, bool)>)
             })
             .unwrap_or(0),
         open.into_iter()
-            .map(|ws| (ws.name, ws.fullscreen))
+            .map(|ws| (ws.id, ws.name, ws.fullscreen, ws.windows))
             .collect(),
     )
 }
@@ -6,7 +6,7 @@ use handlebars::Handlebars;
 use iced::widget::button::Style;
 use iced::widget::{column, container, image, row, scrollable, Container, Text};
 use iced::Length::Fill;
-use iced::{futures::SinkExt, stream, widget::text, Element, Subscription};
+use iced::{futures::SinkExt, mouse, stream, widget::text, Element, Subscription};
 use serde::Deserialize;
 use tokio::{
     io::{AsyncBufReadExt, BufReader},
@@ -15,7 +15,9 @@ use tokio::{
 
 use crate::button::button;
 use crate::config::popup_config::{PopupConfig, PopupConfigOverride};
-use crate::helpers::UnEscapeString;
+use crate::helpers::{Formats, UnEscapeString};
+use crate::menu::{MenuEntry, MenuOnRightClick};
+use crate::rotate::bar_text;
 use crate::{
     config::{
         anchor::BarAnchor,
@@ -24,15 +26,17 @@ use crate::{
     fill::FillExt,
     Message, NERD_FONT,
 };
-use crate::{impl_on_click, impl_wrapper};
+use crate::{impl_on_click, impl_popup_size, impl_wrapper};
 
-use super::Module;
+use super::{Action, ActionContext, Module};
 
 #[derive(Debug, Builder)]
 pub struct MediaMod {
     track: Option<TrackInfo>,
     img: Option<Vec<u8>>,
     active_player: Option<String>,
+    /// The player chosen in the context menu, tracks of other players are ignored
+    selected_player: Option<String>,
     cfg_override: ModuleConfigOverride,
     popup_cfg_override: PopupConfigOverride,
     icon: String,
@@ -41,6 +45,8 @@ pub struct MediaMod {
     max_title_length: usize,
     players: HashSet<String>,
     cover_width: f32,
+    menu: MenuOnRightClick,
+    formats: Formats,
 }
 
 #[derive(Debug)]
@@ -57,6 +63,7 @@ impl Default for MediaMod {
             track: None,
             img: None,
             active_player: None,
+            selected_player: None,
             cfg_override: Default::default(),
             popup_cfg_override: PopupConfigOverride {
                 width: Some(300),
@@ -74,16 +81,21 @@ impl Default for MediaMod {
             max_title_length: 16,
             players: HashSet::from(["spotify".to_string(), "kew".to_string()]),
             cover_width: 260.,
+            menu: MenuOnRightClick,
+            // The left click opens the popup and the right click the menu
+            formats: Formats::new("media").switch_on(mouse::Button::Middle),
         }
     }
 }
 
 impl MediaMod {
-    fn get_active_trimmed(&self) -> Option<String> {
+    /// The title and artist of the track, which are trimmed to the `max_length` and
+    /// `max_title_length` if `trim` is set
+    fn get_active(&self, trim: bool) -> Option<(String, String)> {
         self.track.as_ref().map(|track| {
             let mut title = track.title.clone();
             let mut artist = track.artist.clone();
-            if self.is_overlength() {
+            if trim && self.is_overlength() {
                 if title.len() > self.max_title_length {
                     title = title.chars().take(self.max_title_length - 3).collect();
                     title.push_str("...");
@@ -96,10 +108,7 @@ impl MediaMod {
                     artist.push_str("...");
                 }
             }
-            match track.artist.is_empty() {
-                true => title,
-                false => format!("{} - {}", title, artist),
-            }
+            (title, artist)
         })
     }
 
@@ -110,13 +119,37 @@ impl MediaMod {
     }
 
     fn new_track(&mut self, track: TrackInfo) {
-        if self.players.contains(&track.player) {
+        let selected = self
+            .selected_player
+            .as_ref()
+            .map_or(true, |player| *player == track.player);
+        if selected && self.players.contains(&track.player) {
             self.active_player = Some(track.player.clone());
             self.track = Some(track)
         }
     }
 }
 
+/// Only show tracks of the given player, or of any player
+#[derive(Debug)]
+struct SelectPlayer(Option<String>);
+
+impl Action for SelectPlayer {
+    fn as_message(&self, _: &ActionContext) -> Message {
+        let player = self.0.clone();
+        Message::update(move |reg| {
+            let media = reg.get_module_mut::<MediaMod>();
+            if let Some(player) = &player {
+                if media.track.as_ref().is_some_and(|t| t.player != *player) {
+                    media.track = None;
+                }
+                media.active_player = Some(player.clone());
+            }
+            media.selected_player = player;
+        })
+    }
+}
+
 #[derive(Debug)]
 struct TrackInfo {
     title: String,
@@ -200,8 +233,14 @@ impl Module for MediaMod {
         config: &LocalModuleConfig,
         popup_config: &PopupConfig,
         anchor: &BarAnchor,
-        _handlebars: &Handlebars,
+        handlebars: &Handlebars,
     ) -> Element<'_, Message> {
+        let marquee = self.cfg_override.marquee.unwrap_or(config.marquee);
+        // The format gets the trimmed title and artist
+        let mut ctx = self.context(None);
+        if let Some((title, artist)) = self.get_active(!marquee.enabled) {
+            ctx.extend([("title", title), ("artist", artist)]);
+        }
         button(
             list![
                 anchor,
@@ -213,23 +252,24 @@ impl Module for MediaMod {
                         .font(NERD_FONT)
                 )
                 .padding(self.cfg_override.icon_margin.unwrap_or(config.icon_margin)),
-                container(
-                    text(self.get_active_trimmed().unwrap_or_default())
-                        .fill(anchor)
-                        .size(self.cfg_override.font_size.unwrap_or(config.font_size))
-                        .color(self.cfg_override.text_color.unwrap_or(config.text_color))
-                )
+                container(bar_text(
+                    self.formats.render(handlebars, &ctx),
+                    self.cfg_override.font_size.unwrap_or(config.font_size),
+                    self.cfg_override.text_color.unwrap_or(config.text_color),
+                    anchor,
+                    self.cfg_override.rotate_text.unwrap_or(config.rotate_text),
+                    marquee,
+                ))
                 .padding(self.cfg_override.text_margin.unwrap_or(config.text_margin))
             ]
             .spacing(self.cfg_override.spacing.unwrap_or(config.spacing)),
         )
-        .on_event_maybe_with(self.track.as_ref().map(|_| {
+        .on_event_maybe_try(self.track.as_ref().map(|_| {
             Message::popup::<Self>(
                 self.popup_cfg_override.width.unwrap_or(popup_config.width),
                 self.popup_cfg_override
                     .height
                     .unwrap_or(popup_config.height),
-                anchor,
             )
         }))
         .style(|_, _| Style::default())
@@ -408,6 +448,11 @@ impl Module for MediaMod {
             .get("max_title_length")
             .and_then(|v| v.as_ref().and_then(|v| v.parse().ok()))
             .unwrap_or(default.max_title_length);
+        self.formats.read_config(
+            config,
+            "{{title}}{{#if artist}} - {{artist}}{{/if}}",
+            templates,
+        );
         self.players = popup_config
             .get("players")
             .and_then(|v| {
@@ -463,7 +508,47 @@ impl Module for MediaMod {
             .unwrap_or_else(|e| eprintln!("Failed to parse battery popup time format: {e}"));
     }
 
-    impl_on_click!();
+    impl_on_click!(formats, menu);
+
+    fn next_format(&mut self) {
+        self.formats.next();
+    }
+
+    impl_popup_size!();
+
+    fn menu(&self) -> Vec<MenuEntry> {
+        let mut players = self.players.iter().collect::<Vec<_>>();
+        players.sort();
+        let mark = |selected: bool| if selected { "• " } else { "" };
+        let any = MenuEntry::item(
+            format!("{}Any player", mark(self.selected_player.is_none())),
+            SelectPlayer(None),
+        );
+        let players = players.into_iter().map(|player| {
+            let selected = self.selected_player.as_ref() == Some(player);
+            MenuEntry::item(
+                format!("{}{player}", mark(selected)),
+                SelectPlayer(Some(player.clone())),
+            )
+        });
+        vec![MenuEntry::Submenu {
+            label: "Player".to_string(),
+            entries: std::iter::once(any).chain(players).collect(),
+        }]
+    }
+
+    fn context(&self, _item: Option<usize>) -> BTreeMap<&'static str, String> {
+        let mut ctx = BTreeMap::from([("player", self.active_player.clone().unwrap_or_default())]);
+        if let Some(track) = &self.track {
+            ctx.extend([
+                ("title", track.title.clone()),
+                ("artist", track.artist.clone()),
+                ("album", track.album.clone()),
+                ("paused", track.paused.to_string()),
+            ]);
+        }
+        ctx
+    }
 
     fn subscription(&self) -> Option<iced::Subscription<Message>> {
         Some(Subscription::run(|| {
@@ -1,27 +1,120 @@
-use std::{collections::HashMap, process::Command};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::{self, BufRead, BufReader},
+    time::Duration,
+};
 
 use bar_rs_derive::Builder;
 use handlebars::Handlebars;
-use iced::widget::container;
-use iced::{widget::text, Element};
+use iced::widget::{button::Style, container, scrollable, text, Container, Text};
+use iced::{futures::SinkExt, mouse, stream, Element, Subscription};
+use tokio::time::sleep;
 
-use crate::config::popup_config::PopupConfig;
 use crate::{
+    button::button,
     config::{
         anchor::BarAnchor,
         module_config::{LocalModuleConfig, ModuleConfigOverride},
+        popup_config::{PopupConfig, PopupConfigOverride},
     },
     fill::FillExt,
-    Message, NERD_FONT,
+    helpers::{Formats, Thresholds, UnEscapeString},
+    impl_on_click, impl_popup_size, impl_wrapper, Message, NERD_FONT,
 };
-use crate::{impl_on_click, impl_wrapper};
 
 use super::Module;
 
-#[derive(Debug, Default, Builder)]
+#[derive(Debug, Builder)]
 pub struct MemoryMod {
+    stats: MemoryStats,
     cfg_override: ModuleConfigOverride,
+    popup_cfg_override: PopupConfigOverride,
     icon: Option<String>,
+    formats: Formats,
+    thresholds: Thresholds,
+}
+
+impl Default for MemoryMod {
+    fn default() -> Self {
+        Self {
+            stats: MemoryStats::default(),
+            cfg_override: Default::default(),
+            popup_cfg_override: PopupConfigOverride {
+                width: Some(250),
+                height: Some(250),
+                ..Default::default()
+            },
+            icon: None,
+            formats: Formats::new("memory").switch_on(mouse::Button::Right),
+            thresholds: Thresholds::new("used_perc"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// All values are represented in kibibytes, as reported by `/proc/meminfo`
+struct MemoryStats {
+    total: u64,
+    free: u64,
+    available: u64,
+    cached: u64,
+    buffers: u64,
+    swap_total: u64,
+    swap_free: u64,
+    zram_total: u64,
+    zram_used: u64,
+}
+
+impl MemoryStats {
+    fn used(&self) -> u64 {
+        self.total.saturating_sub(self.available)
+    }
+
+    fn swap_used(&self) -> u64 {
+        self.swap_total.saturating_sub(self.swap_free)
+    }
+}
+
+impl From<MemoryStats> for BTreeMap<&'static str, String> {
+    fn from(stats: MemoryStats) -> Self {
+        let mib = |kib: u64| (kib / 1024).to_string();
+        let gib = |kib: u64| format!("{:.1}", kib as f32 / 1024. / 1024.);
+        let perc = |part: u64, total: u64| match total {
+            0 => "0".to_string(),
+            total => ((part as f32 / total as f32) * 100.).round().to_string(),
+        };
+        let used = stats.used();
+        let swap_used = stats.swap_used();
+        BTreeMap::from([
+            ("total", mib(stats.total)),
+            ("total_gb", gib(stats.total)),
+            ("used", mib(used)),
+            ("used_gb", gib(used)),
+            ("used_perc", perc(used, stats.total)),
+            ("free", mib(stats.free)),
+            ("free_gb", gib(stats.free)),
+            ("available", mib(stats.available)),
+            ("available_gb", gib(stats.available)),
+            ("available_perc", perc(stats.available, stats.total)),
+            ("cached", mib(stats.cached)),
+            ("cached_gb", gib(stats.cached)),
+            ("buffers", mib(stats.buffers)),
+            ("buffers_gb", gib(stats.buffers)),
+            ("swap_total", mib(stats.swap_total)),
+            ("swap_total_gb", gib(stats.swap_total)),
+            ("swap_used", mib(swap_used)),
+            ("swap_used_gb", gib(swap_used)),
+            ("swap_free", mib(stats.swap_free)),
+            ("swap_free_gb", gib(stats.swap_free)),
+            ("swap_perc", perc(swap_used, stats.swap_total)),
+            ("zram_total", mib(stats.zram_total)),
+            ("zram_total_gb", gib(stats.zram_total)),
+            ("zram_used", mib(stats.zram_used)),
+            ("zram_used_gb", gib(stats.zram_used)),
+            ("zram_perc", perc(stats.zram_used, stats.zram_total)),
+        ])
+    }
 }
 
 impl Module for MemoryMod {
@@ -32,58 +125,213 @@ impl Module for MemoryMod {
     fn view(
         &self,
         config: &LocalModuleConfig,
-        _popup_config: &PopupConfig,
+        popup_config: &PopupConfig,
         anchor: &BarAnchor,
-        _handlebars: &Handlebars,
+        handlebars: &Handlebars,
     ) -> Element<'_, Message> {
-        let usage = Command::new("sh")
-            .arg("-c")
-            .arg("free | grep Mem | awk '{printf \"%.0f\", $3/$2 * 100.0}'")
-            .output()
-            .map(|out| String::from_utf8_lossy(&out.stdout).to_string())
-            .unwrap_or_else(|e| {
-                eprintln!("Failed to get memory usage. err: {e}");
-                "0".to_string()
-            })
-            .parse()
-            .unwrap_or_else(|e| {
-                eprintln!("Failed to parse memory usage (output from free), e: {e}");
-                999
-            });
-
-        list![
-            anchor,
+        let style = self.thresholds.style(&self.context(None));
+        let ctx: BTreeMap<&'static str, String> = self.stats.into();
+        let format = self.formats.render(handlebars, &ctx);
+        button(
+            list![
+                anchor,
+                container(
+                    text!("{}", self.icon.as_ref().unwrap_or(&"󰍛".to_string()))
+                        .fill(anchor)
+                        .size(self.cfg_override.icon_size.unwrap_or(config.icon_size))
+                        .color(
+                            style
+                                .icon_color
+                                .or(self.cfg_override.icon_color)
+                                .unwrap_or(config.icon_color)
+                        )
+                        .font(NERD_FONT)
+                )
+                .padding(self.cfg_override.icon_margin.unwrap_or(config.icon_margin)),
+                container(
+                    text(format)
+                        .fill(anchor)
+                        .size(self.cfg_override.font_size.unwrap_or(config.font_size))
+                        .color(
+                            style
+                                .text_color
+                                .or(self.cfg_override.text_color)
+                                .unwrap_or(config.text_color)
+                        )
+                )
+                .padding(self.cfg_override.text_margin.unwrap_or(config.text_margin)),
+            ]
+            .spacing(self.cfg_override.spacing.unwrap_or(config.spacing)),
+        )
+        .on_event_try(Message::popup::<Self>(
+            self.popup_cfg_override.width.unwrap_or(popup_config.width),
+            self.popup_cfg_override
+                .height
+                .unwrap_or(popup_config.height),
+        ))
+        .style(|_, _| Style::default())
+        .into()
+    }
+
+    fn popup_view<'a>(
+        &'a self,
+        config: &'a PopupConfig,
+        template: &Handlebars,
+    ) -> Element<'a, Message> {
+        let fmt_text = |text: Text<'a>| -> Container<'a, Message> {
             container(
-                text!("{}", self.icon.as_ref().unwrap_or(&"󰍛".to_string()))
-                    .fill(anchor)
-                    .size(self.cfg_override.icon_size.unwrap_or(config.icon_size))
-                    .color(self.cfg_override.icon_color.unwrap_or(config.icon_color))
-                    .font(NERD_FONT)
+                text.size(
+                    self.popup_cfg_override
+                        .font_size
+                        .unwrap_or(config.font_size),
+                )
+                .color(
+                    self.popup_cfg_override
+                        .text_color
+                        .unwrap_or(config.text_color),
+                ),
             )
-            .padding(self.cfg_override.icon_margin.unwrap_or(config.icon_margin)),
-            container(
-                text!["{}%", usage]
-                    .fill(anchor)
-                    .size(self.cfg_override.font_size.unwrap_or(config.font_size))
-                    .color(self.cfg_override.text_color.unwrap_or(config.text_color))
+            .padding(
+                self.popup_cfg_override
+                    .text_margin
+                    .unwrap_or(config.text_margin),
             )
-            .padding(self.cfg_override.text_margin.unwrap_or(config.text_margin)),
-        ]
-        .spacing(self.cfg_override.spacing.unwrap_or(config.spacing))
-        .into()
+        };
+        let ctx: BTreeMap<&'static str, String> = self.stats.into();
+        let format = template
+            .render("memory_popup", &ctx)
+            .map_err(|e| eprintln!("Failed to render memory stats: {e}"))
+            .unwrap_or_default();
+        container(scrollable(fmt_text(text(format))))
+            .padding(self.popup_cfg_override.padding.unwrap_or(config.padding))
+            .style(|_| container::Style {
+                background: Some(
+                    self.popup_cfg_override
+                        .background
+                        .unwrap_or(config.background),
+                ),
+                border: self.popup_cfg_override.border.unwrap_or(config.border),
+                ..Default::default()
+            })
+            .fill_maybe(
+                self.popup_cfg_override
+                    .fill_content_to_size
+                    .unwrap_or(config.fill_content_to_size),
+            )
+            .into()
     }
 
-    impl_wrapper!();
+    impl_wrapper!(thresholds);
 
     fn read_config(
         &mut self,
         config: &HashMap<String, Option<String>>,
-        _popup_config: &HashMap<String, Option<String>>,
-        _templates: &mut Handlebars,
+        popup_config: &HashMap<String, Option<String>>,
+        templates: &mut Handlebars,
     ) {
         self.cfg_override = config.into();
+        self.thresholds.read_config(config);
+        self.popup_cfg_override.update(popup_config);
         self.icon = config.get("icon").and_then(|v| v.clone());
+        self.formats
+            .read_config(config, "{{used_perc}}%", templates);
+        templates
+            .register_template_string(
+                "memory_popup",
+                popup_config
+                    .get("format")
+                    .unescape()
+                    .unwrap_or("Used: {{used_gb}} GiB ({{used_perc}}%)\nAvailable: {{available_gb}} GiB\nCached: {{cached_gb}} GiB\nBuffers: {{buffers_gb}} GiB\nTotal: {{total_gb}} GiB\n\nSwap: {{swap_used_gb}} / {{swap_total_gb}} GiB ({{swap_perc}}%)\nZram: {{zram_used_gb}} / {{zram_total_gb}} GiB".to_string()),
+            )
+            .unwrap_or_else(|e| eprintln!("Failed to parse memory popup format: {e}"));
+    }
+
+    impl_on_click!(formats);
+
+    fn next_format(&mut self) {
+        self.formats.next();
+    }
+
+    impl_popup_size!();
+
+    fn context(&self, _item: Option<usize>) -> BTreeMap<&'static str, String> {
+        let mut ctx: BTreeMap<&'static str, String> = self.stats.into();
+        ctx.insert("state", self.thresholds.state(&ctx).to_string());
+        ctx
     }
 
-    impl_on_click!();
+    fn subscription(&self) -> Option<iced::Subscription<Message>> {
+        Some(Subscription::run(|| {
+            stream::channel(1, |mut sender| async move {
+                let interval: u64 = 2000;
+                loop {
+                    let Ok(stats) = read_stats().map_err(|e| {
+                        eprintln!("Failed to read memory stats from /proc/meminfo: {e}")
+                    }) else {
+                        return;
+                    };
+
+                    sender
+                        .send(Message::update(move |reg| {
+                            reg.get_module_mut::<MemoryMod>().stats = stats
+                        }))
+                        .await
+                        .unwrap_or_else(|err| {
+                            eprintln!("Trying to send memory stats failed with err: {err}");
+                        });
+
+                    sleep(Duration::from_millis(interval)).await;
+                }
+            })
+        }))
+    }
+}
+
+/// Read memory statistics from `/proc/meminfo` and zram swap devices from `/proc/swaps`, see
+/// https://docs.kernel.org/filesystems/proc.html#meminfo
+fn read_stats() -> Result<MemoryStats, io::Error> {
+    let reader = BufReader::new(fs::File::open("/proc/meminfo")?);
+    let mut stats = MemoryStats::default();
+    let mut reclaimable = 0;
+    for line in reader.lines() {
+        let line = line?;
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let Some(value) = value
+            .split_whitespace()
+            .next()
+            .and_then(|v| v.parse::<u64>().ok())
+        else {
+            continue;
+        };
+        match key {
+            "MemTotal" => stats.total = value,
+            "MemFree" => stats.free = value,
+            "MemAvailable" => stats.available = value,
+            "Buffers" => stats.buffers = value,
+            "Cached" => stats.cached = value,
+            "SReclaimable" => reclaimable = value,
+            "SwapTotal" => stats.swap_total = value,
+            "SwapFree" => stats.swap_free = value,
+            _ => {}
+        }
+    }
+    // `free` reports the page cache including reclaimable slab memory
+    stats.cached += reclaimable;
+
+    // /proc/swaps lists the size and usage of every swap device in kibibytes:
+    // Filename     Type        Size    Used    Priority
+    // /dev/zram0   partition   8388604 0       100
+    if let Ok(swaps) = fs::read_to_string("/proc/swaps") {
+        for line in swaps.lines().skip(1) {
+            if let [name, _, size, used, ..] = line.split_whitespace().collect::<Vec<&str>>()[..] {
+                if name.starts_with("/dev/zram") {
+                    stats.zram_total += size.parse().unwrap_or(0);
+                    stats.zram_used += used.parse().unwrap_or(0);
+                }
+            }
+        }
+    }
+    Ok(stats)
 }
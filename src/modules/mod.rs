@@ -1,9 +1,11 @@
 use std::{
     any::{Any, TypeId},
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt::Debug,
+    process::Command,
 };
 
+use backlight::BacklightMod;
 use battery::BatteryMod;
 use bluetooth::BluetoothMod;
 use cpu::CpuMod;
@@ -13,11 +15,7 @@ use downcast_rs::{impl_downcast, Downcast};
 use empty::EmptyModule;
 use handlebars::Handlebars;
 use hyprland::{window::HyprWindowMod, workspaces::HyprWorkspaceMod};
-use iced::{
-    theme::Palette,
-    widget::{container, Container},
-    Alignment, Color, Event, Theme,
-};
+use iced::{theme::Palette, widget::container, Alignment, Color, Event, Rectangle, Theme};
 use iced::{widget::container::Style, Element, Subscription};
 use media::MediaMod;
 use memory::MemoryMod;
@@ -27,13 +25,22 @@ use volume::VolumeMod;
 use wayfire::{WayfireWindowMod, WayfireWorkspaceMod};
 
 use crate::{
-    config::{anchor::BarAnchor, module_config::LocalModuleConfig, popup_config::PopupConfig},
+    actions::parse_action,
+    button::{ButtonEvent, ScrollDirection},
+    config::{
+        anchor::BarAnchor,
+        animation_config::AnimationConfig,
+        module_config::{LocalModuleConfig, ModuleConfigOverride},
+        popup_config::PopupConfig,
+    },
     fill::FillExt,
     listeners::Listener,
+    menu::MenuEntry,
     registry::Registry,
     Message,
 };
 
+pub mod backlight;
 pub mod battery;
 pub mod bluetooth;
 pub mod cpu;
@@ -86,6 +93,10 @@ pub trait Module: Any + Debug + Send + Sync + Downcast {
         .padding(config.margin)
         .into()
     }
+    /// The size the module needs across the bar, used if the bar is sized automatically
+    fn thickness(&self, config: &LocalModuleConfig, anchor: &BarAnchor) -> f32 {
+        config.thickness(&ModuleConfigOverride::default(), anchor.vertical())
+    }
     /// The module may optionally have a subscription listening for external events.
     /// See [passive-subscriptions](https://docs.iced.rs/iced/#passive-subscriptions).
     fn subscription(&self) -> Option<Subscription<Message>> {
@@ -106,10 +117,17 @@ pub trait Module: Any + Debug + Send + Sync + Downcast {
     ) {
     }
     #[allow(unused_variables)]
+    /// Apply the `[animations]` settings, for modules which animate changes of their values
+    fn set_animation(&mut self, config: &AnimationConfig) {}
+    /// Whether an animation of this module is running, the bar is redrawn every frame meanwhile
+    fn animating(&self) -> bool {
+        false
+    }
+    #[allow(unused_variables)]
     /// The action to perform on an on_click event
     fn on_click<'a>(
         &'a self,
-        event: iced::Event,
+        event: &ButtonEvent,
         config: &'a LocalModuleConfig,
     ) -> Option<&'a dyn Action> {
         None
@@ -117,6 +135,24 @@ pub trait Module: Any + Debug + Send + Sync + Downcast {
     #[allow(unused_variables, dead_code)]
     /// Handle an action (likely produced by a user interaction).
     fn handle_action(&mut self, action: &dyn Action) {}
+    /// Switch to the next format, for modules which have several (see `format:next`).
+    fn next_format(&mut self) {}
+    #[allow(unused_variables)]
+    /// Variables describing the state of this module, which are passed to its actions.
+    /// `item` is the index of the element of the module under the cursor, e.g. a workspace.
+    fn context(&self, item: Option<usize>) -> BTreeMap<&'static str, String> {
+        BTreeMap::new()
+    }
+    /// Entries this module adds to its context menu, below the entries of its
+    /// `[module_menu:<name>]` section. They are collected when the menu opens.
+    fn menu(&self) -> Vec<MenuEntry> {
+        vec![]
+    }
+    #[allow(unused_variables)]
+    /// The size of the popup, if this module has one.
+    fn popup_size(&self, config: &PopupConfig) -> Option<(i32, i32)> {
+        None
+    }
     #[allow(unused_variables)]
     /// The view of a popup
     fn popup_view<'a>(
@@ -133,15 +169,7 @@ pub trait Module: Any + Debug + Send + Sync + Downcast {
         anchor: &BarAnchor,
         template: &Handlebars,
     ) -> Element<'a, Message> {
-        let align = |elem: Container<'a, Message>| -> Container<'a, Message> {
-            match anchor {
-                BarAnchor::Top => elem.align_y(Alignment::Start),
-                BarAnchor::Bottom => elem.align_y(Alignment::End),
-                BarAnchor::Left => elem.align_x(Alignment::Start),
-                BarAnchor::Right => elem.align_x(Alignment::End),
-            }
-        };
-        align(container(self.popup_view(config, template)).fill(anchor)).into()
+        align_popup(self.popup_view(config, template), anchor)
     }
     /// The theme of a popup
     fn popup_theme(&self) -> Theme {
@@ -160,37 +188,122 @@ pub trait Module: Any + Debug + Send + Sync + Downcast {
 impl_downcast!(Module);
 
 pub trait Action: Any + Debug + Send + Sync + Downcast {
-    fn as_message(&self) -> Message;
+    fn as_message(&self, ctx: &ActionContext) -> Message;
 }
 impl_downcast!(Action);
 
+/// Information about the module an action was triggered on
+#[derive(Debug, Clone)]
+pub struct ActionContext {
+    pub module: TypeId,
+    /// The bounds of the module on the bar
+    pub bounds: Rectangle,
+    /// See [Module::context]
+    pub vars: BTreeMap<&'static str, String>,
+}
+
 impl From<&String> for Box<dyn Action> {
     fn from(value: &String) -> Box<dyn Action> {
-        Box::new(CommandAction(value.clone()))
+        parse_action(value)
     }
 }
 
 #[derive(Debug)]
-pub struct CommandAction(String);
+pub struct CommandAction(pub String);
 
 impl Action for CommandAction {
-    fn as_message(&self) -> Message {
-        Message::command_sh(&self.0)
+    fn as_message(&self, ctx: &ActionContext) -> Message {
+        // The command is rendered by the bar, which has all template helpers registered
+        Message::SpawnTemplate(self.0.clone(), ctx.vars.clone())
+    }
+}
+
+impl CommandAction {
+    /// Render the `{{value}}`s of a command, which are quoted for the shell. The values are also
+    /// passed as `BAR_*` environment variables.
+    pub fn command(
+        command: &str,
+        vars: &BTreeMap<&'static str, String>,
+        templates: &mut Handlebars,
+    ) -> Command {
+        let rendered = match command.contains("{{") {
+            true => {
+                templates.register_escape_fn(shell_quote);
+                let rendered = templates
+                    .render_template(command, vars)
+                    .map_err(|e| eprintln!("Failed to render the command {command}: {e}"))
+                    .unwrap_or_default();
+                // Formats and popups keep the default escaping
+                templates.unregister_escape_fn();
+                rendered
+            }
+            false => command.to_string(),
+        };
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(rendered).envs(
+            vars.iter()
+                .map(|(key, value)| (format!("BAR_{}", key.to_uppercase()), value)),
+        );
+        cmd
     }
 }
 
+/// Quote a value, so that the shell treats it as a single word
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
 #[derive(Debug, Default)]
 pub struct OnClickAction {
     pub left: Option<Box<dyn Action>>,
     pub center: Option<Box<dyn Action>>,
     pub right: Option<Box<dyn Action>>,
+    pub double: Option<Box<dyn Action>>,
+    pub shift_left: Option<Box<dyn Action>>,
+    pub ctrl_left: Option<Box<dyn Action>>,
+    pub scroll_up: Option<Box<dyn Action>>,
+    pub scroll_down: Option<Box<dyn Action>>,
+    pub scroll_left: Option<Box<dyn Action>>,
+    pub scroll_right: Option<Box<dyn Action>>,
 }
 
 impl OnClickAction {
-    pub fn event(&self, event: Event) -> Option<&dyn Action> {
-        match event {
+    pub fn is_empty(&self) -> bool {
+        [
+            &self.left,
+            &self.center,
+            &self.right,
+            &self.double,
+            &self.shift_left,
+            &self.ctrl_left,
+            &self.scroll_up,
+            &self.scroll_down,
+            &self.scroll_left,
+            &self.scroll_right,
+        ]
+        .iter()
+        .all(|action| action.is_none())
+    }
+
+    pub fn event(&self, event: &ButtonEvent) -> Option<&dyn Action> {
+        if let Some(direction) = event.scroll {
+            return match direction {
+                ScrollDirection::Up => self.scroll_up.as_deref(),
+                ScrollDirection::Down => self.scroll_down.as_deref(),
+                ScrollDirection::Left => self.scroll_left.as_deref(),
+                ScrollDirection::Right => self.scroll_right.as_deref(),
+            };
+        }
+        match event.event {
             Event::Mouse(iced::mouse::Event::ButtonReleased(iced::mouse::Button::Left)) => {
-                self.left.as_deref()
+                // Fall back to the plain click if there is no action for this kind of click
+                let special = match event {
+                    e if e.double_click => self.double.as_deref(),
+                    e if e.modifiers.control() => self.ctrl_left.as_deref(),
+                    e if e.modifiers.shift() => self.shift_left.as_deref(),
+                    _ => None,
+                };
+                special.or(self.left.as_deref())
             }
             Event::Mouse(iced::mouse::Event::ButtonReleased(iced::mouse::Button::Middle)) => {
                 self.center.as_deref()
@@ -203,6 +316,21 @@ impl OnClickAction {
     }
 }
 
+/// Place the content of a popup at the side of the popup window which faces the bar
+pub fn align_popup<'a>(
+    content: impl Into<Element<'a, Message>>,
+    anchor: &BarAnchor,
+) -> Element<'a, Message> {
+    let popup = container(content).fill(anchor);
+    match anchor {
+        BarAnchor::Top => popup.align_y(Alignment::Start),
+        BarAnchor::Bottom => popup.align_y(Alignment::End),
+        BarAnchor::Left => popup.align_x(Alignment::Start),
+        BarAnchor::Right => popup.align_x(Alignment::End),
+    }
+    .into()
+}
+
 pub fn require_listener<T>() -> TypeId
 where
     T: Listener,
@@ -217,6 +345,7 @@ pub fn register_modules(registry: &mut Registry) {
     registry.register_module::<BatteryMod>();
     registry.register_module::<BluetoothMod>();
     registry.register_module::<VolumeMod>();
+    registry.register_module::<BacklightMod>();
     registry.register_module::<MediaMod>();
     registry.register_module::<DateMod>();
     registry.register_module::<TimeMod>();
@@ -232,22 +361,54 @@ pub fn register_modules(registry: &mut Registry) {
 #[macro_export]
 macro_rules! impl_wrapper {
     () => {
+        $crate::impl_wrapper!(@style |_| $crate::helpers::StateStyle::default());
+    };
+    // Style the module depending on the state of its thresholds
+    ($thresholds:ident) => {
+        $crate::impl_wrapper!(@style |module: &Self| {
+            module.$thresholds.style(&module.context(None))
+        });
+    };
+    (@style $style:expr) => {
         fn wrapper<'a>(
             &'a self,
             config: &'a LocalModuleConfig,
             content: Element<'a, Message>,
             anchor: &BarAnchor,
         ) -> Element<'a, Message> {
+            let style: $crate::helpers::StateStyle = ($style)(self);
             iced::widget::container(
                 $crate::button::button(content)
                     .fill(anchor)
                     .padding(self.cfg_override.padding.unwrap_or(config.padding))
-                    .on_event_try(|evt, _, _, _, _| {
-                        self.on_click(evt, config).map(|evt| evt.as_message())
+                    .on_event_try(|evt, layout, cursor, _, _| {
+                        self.on_click(&evt, config).map(|action| {
+                            // The element of the module content under the cursor
+                            let item = cursor.position().and_then(|position| {
+                                layout
+                                    .children()
+                                    .next()?
+                                    .children()
+                                    .position(|child| child.bounds().contains(position))
+                            });
+                            let mut vars = self.context(item);
+                            vars.insert("module", self.name());
+                            action.as_message(&$crate::modules::ActionContext {
+                                module: std::any::TypeId::of::<Self>(),
+                                bounds: layout.bounds(),
+                                vars,
+                            })
+                        })
                     })
-                    .style(|_, _| iced::widget::button::Style {
-                        background: self.cfg_override.background.unwrap_or(config.background),
-                        border: self.cfg_override.border.unwrap_or(config.border),
+                    .style(move |_, _| iced::widget::button::Style {
+                        background: style
+                            .background
+                            .or(self.cfg_override.background)
+                            .unwrap_or(config.background),
+                        border: style
+                            .border
+                            .or(self.cfg_override.border)
+                            .unwrap_or(config.border),
                         ..Default::default()
                     }),
             )
@@ -255,6 +416,25 @@ macro_rules! impl_wrapper {
             .padding(self.cfg_override.margin.unwrap_or(config.margin))
             .into()
         }
+
+        fn thickness(&self, config: &LocalModuleConfig, anchor: &BarAnchor) -> f32 {
+            config.thickness(&self.cfg_override, anchor.vertical())
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! impl_popup_size {
+    () => {
+        fn popup_size(
+            &self,
+            config: &$crate::config::popup_config::PopupConfig,
+        ) -> Option<(i32, i32)> {
+            Some((
+                self.popup_cfg_override.width.unwrap_or(config.width),
+                self.popup_cfg_override.height.unwrap_or(config.height),
+            ))
+        }
     };
 }
 
@@ -263,7 +443,21 @@ macro_rules! impl_on_click {
     () => {
         fn on_click<'a>(
             &'a self,
-            event: iced::Event,
+            event: &$crate::button::ButtonEvent,
+            config: &'a LocalModuleConfig,
+        ) -> Option<&'a dyn $crate::modules::Action> {
+            self.cfg_override
+                .action
+                .as_ref()
+                .unwrap_or(&config.action)
+                .event(event)
+        }
+    };
+    // Fall back to the given built-in actions of the module, if the user didn't configure any
+    ($($default:ident),+) => {
+        fn on_click<'a>(
+            &'a self,
+            event: &$crate::button::ButtonEvent,
             config: &'a LocalModuleConfig,
         ) -> Option<&'a dyn $crate::modules::Action> {
             self.cfg_override
@@ -271,6 +465,7 @@ macro_rules! impl_on_click {
                 .as_ref()
                 .unwrap_or(&config.action)
                 .event(event)
+                $(.or_else(|| self.$default.event(event)))+
         }
     };
 }
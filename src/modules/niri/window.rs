@@ -5,12 +5,14 @@ use bar_rs_derive::Builder;
 use handlebars::Handlebars;
 use iced::widget::button::Style;
 use iced::widget::{container, scrollable, text};
-use iced::Element;
+use iced::{mouse, Element};
 use niri_ipc::Window;
 
 use crate::button::button;
 use crate::config::popup_config::{PopupConfig, PopupConfigOverride};
-use crate::helpers::UnEscapeString;
+use crate::helpers::{Formats, UnEscapeString};
+use crate::marquee::trim;
+use crate::rotate::bar_text;
 use crate::{
     config::{
         anchor::BarAnchor,
@@ -19,20 +21,26 @@ use crate::{
     },
     fill::FillExt,
     listeners::niri::NiriListener,
-    modules::{require_listener, Module},
+    modules::{niri::NiriWorkspaceMod, require_listener, Module},
+    registry::Registry,
     Message,
 };
-use crate::{impl_on_click, impl_wrapper};
+use crate::{impl_on_click, impl_popup_size, impl_wrapper};
 
 #[derive(Debug, Builder)]
 pub struct NiriWindowMod {
     // (title, app_id)
     pub windows: HashMap<u64, Window>,
     pub focused: Option<u64>,
+    /// Whether the focused window covers its whole output
+    pub fullscreen: bool,
+    /// The number of windows on the focused workspace
+    pub workspace_windows: usize,
     max_length: usize,
     show_app_id: bool,
     cfg_override: ModuleConfigOverride,
     popup_cfg_override: PopupConfigOverride,
+    formats: Formats,
 }
 
 impl Default for NiriWindowMod {
@@ -40,6 +48,8 @@ impl Default for NiriWindowMod {
         Self {
             windows: HashMap::new(),
             focused: None,
+            fullscreen: false,
+            workspace_windows: 0,
             max_length: 25,
             show_app_id: false,
             cfg_override: Default::default(),
@@ -48,30 +58,22 @@ impl Default for NiriWindowMod {
                 height: Some(250),
                 ..Default::default()
             },
+            formats: Formats::new("niri.window").switch_on(mouse::Button::Right),
         }
     }
 }
 
 impl NiriWindowMod {
-    fn get_title(&self) -> Option<&String> {
-        self.focused.and_then(|id| {
-            self.windows.get(&id).and_then(|w| match self.show_app_id {
-                true => w.app_id.as_ref(),
-                false => w.title.as_ref(),
-            })
-        })
-    }
-
-    fn trimmed_title(&self) -> String {
-        self.get_title()
-            .map(|title| match title.len() > self.max_length {
-                true => format!(
-                    "{}...",
-                    &title.chars().take(self.max_length - 3).collect::<String>()
-                ),
-                false => title.to_string(),
-            })
-            .unwrap_or_default()
+    /// Update the state which depends on the workspaces. Whether the focused window is
+    /// fullscreen is set by the listener.
+    pub fn update_state(reg: &mut Registry) {
+        let focused_ws = reg.get_module::<NiriWorkspaceMod>().focused;
+        let window_mod = reg.get_module_mut::<NiriWindowMod>();
+        window_mod.workspace_windows = window_mod
+            .windows
+            .values()
+            .filter(|window| window.workspace_id == Some(focused_ws))
+            .count();
     }
 }
 
@@ -89,21 +91,27 @@ impl Module for NiriWindowMod {
         config: &LocalModuleConfig,
         popup_config: &PopupConfig,
         anchor: &BarAnchor,
-        _handlebars: &Handlebars,
+        handlebars: &Handlebars,
     ) -> Element<'_, Message> {
-        button(
-            text(self.trimmed_title())
-                .size(self.cfg_override.font_size.unwrap_or(config.font_size))
-                .color(self.cfg_override.text_color.unwrap_or(config.text_color))
-                .fill(anchor),
-        )
+        let title = self.formats.render(handlebars, &self.context(None));
+        let marquee = self.cfg_override.marquee.unwrap_or(config.marquee);
+        button(bar_text(
+            match marquee.enabled {
+                true => title,
+                false => trim(&title, self.max_length),
+            },
+            self.cfg_override.font_size.unwrap_or(config.font_size),
+            self.cfg_override.text_color.unwrap_or(config.text_color),
+            anchor,
+            self.cfg_override.rotate_text.unwrap_or(config.rotate_text),
+            marquee,
+        ))
         .padding(self.cfg_override.text_margin.unwrap_or(config.text_margin))
-        .on_event_with(Message::popup::<Self>(
+        .on_event_try(Message::popup::<Self>(
             self.popup_cfg_override.width.unwrap_or(popup_config.width),
             self.popup_cfg_override
                 .height
                 .unwrap_or(popup_config.height),
-            anchor,
         ))
         .style(|_, _| Style::default())
         .into()
@@ -126,7 +134,11 @@ impl Module for NiriWindowMod {
                         ("window_id", &window_id),
                         ("workspace_id", &workspace_id),
                     ]);
-                    text(template.render("niri.window", &ctx).unwrap_or_default())
+                    text(
+                        template
+                            .render("niri.window_popup", &ctx)
+                            .unwrap_or_default(),
+                    )
                 } else {
                     "No window focused".into()
                 }
@@ -188,9 +200,14 @@ impl Module for NiriWindowMod {
             .get("show_app_id")
             .and_then(|v| v.into_bool())
             .unwrap_or(default.show_app_id);
+        let format = match self.show_app_id {
+            true => "{{app_id}}",
+            false => "{{title}}",
+        };
+        self.formats.read_config(config, format, templates);
         templates
             .register_template_string(
-                "niri.window",
+                "niri.window_popup",
                 popup_config
                     .get("format")
                     .unescape()
@@ -199,5 +216,25 @@ impl Module for NiriWindowMod {
             .unwrap_or_else(|e| eprintln!("Failed to parse battery popup format: {e}"));
     }
 
-    impl_on_click!();
+    impl_on_click!(formats);
+
+    fn next_format(&mut self) {
+        self.formats.next();
+    }
+
+    impl_popup_size!();
+
+    fn context(&self, _item: Option<usize>) -> BTreeMap<&'static str, String> {
+        let mut ctx = BTreeMap::from([("windows", self.workspace_windows.to_string())]);
+        if let Some(window) = self.focused.and_then(|id| self.windows.get(&id)) {
+            ctx.extend([
+                ("id", window.id.to_string()),
+                ("title", window.title.clone().unwrap_or_default()),
+                ("app_id", window.app_id.clone().unwrap_or_default()),
+                ("fullscreen", self.fullscreen.to_string()),
+                ("floating", window.is_floating.to_string()),
+            ]);
+        }
+        ctx
+    }
 }
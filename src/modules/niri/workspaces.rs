@@ -1,6 +1,6 @@
 use std::{
     any::{Any, TypeId},
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     sync::Arc,
 };
 
@@ -14,8 +14,10 @@ use niri_ipc::Workspace;
 use tokio::sync::broadcast;
 
 use crate::{
+    animation::{Animated, Highlight},
     config::{
         anchor::BarAnchor,
+        animation_config::AnimationConfig,
         module_config::{LocalModuleConfig, ModuleConfigOverride},
         parse::StringExt,
         popup_config::PopupConfig,
@@ -23,7 +25,7 @@ use crate::{
     fill::FillExt,
     impl_on_click, impl_wrapper, list,
     listeners::niri::NiriListener,
-    modules::{require_listener, Module},
+    modules::{require_listener, Action, ActionContext, Module, OnClickAction},
     Message, NERD_FONT,
 };
 
@@ -31,6 +33,8 @@ use crate::{
 pub struct NiriWorkspaceMod {
     pub workspaces: HashMap<String, Vec<Workspace>>,
     pub focused: u64,
+    /// The position of the highlight of the focused workspace, which slides between workspaces
+    highlight: Animated<f32>,
     pub sender: broadcast::Sender<Arc<dyn Any + Send + Sync>>,
     cfg_override: ModuleConfigOverride,
     icon_padding: Padding,
@@ -46,6 +50,7 @@ pub struct NiriWorkspaceMod {
     fallback_icon: String,
     active_fallback_icon: String,
     output_order: Vec<String>,
+    default_action: OnClickAction,
 }
 
 impl Default for NiriWorkspaceMod {
@@ -53,6 +58,7 @@ impl Default for NiriWorkspaceMod {
         Self {
             workspaces: HashMap::new(),
             focused: 0,
+            highlight: Animated::default(),
             sender: broadcast::channel(1).0,
             cfg_override: Default::default(),
             icon_padding: Padding::default(),
@@ -67,28 +73,60 @@ impl Default for NiriWorkspaceMod {
             fallback_icon: String::from(""),
             active_fallback_icon: String::from(""),
             output_order: vec![],
+            default_action: OnClickAction {
+                scroll_up: Some(Box::new(FocusWorkspace(
+                    niri_ipc::Action::FocusWorkspaceUp {},
+                ))),
+                scroll_down: Some(Box::new(FocusWorkspace(
+                    niri_ipc::Action::FocusWorkspaceDown {},
+                ))),
+                ..Default::default()
+            },
         }
     }
 }
 
+/// Sends the given action to niri, to move the focus to another workspace
+#[derive(Debug)]
+struct FocusWorkspace(niri_ipc::Action);
+
+impl Action for FocusWorkspace {
+    fn as_message(&self, _ctx: &ActionContext) -> Message {
+        let action = self.0.clone();
+        Message::action(move |reg| {
+            reg.get_module::<NiriWorkspaceMod>()
+                .sender
+                .send(Arc::new(action))
+                .ok();
+        })
+    }
+}
+
 impl NiriWorkspaceMod {
-    fn sort_by_outputs<'a, F, I>(&'a self, f: F) -> Vec<Element<'a, Message>>
+    /// Move the highlight to the focused workspace
+    pub fn update_highlight(&mut self) {
+        let idx = self
+            .sort_by_outputs(|(_, workspaces)| workspaces.iter())
+            .iter()
+            .position(|ws| ws.id == self.focused);
+        if let Some(idx) = idx {
+            self.highlight.set(idx as f32);
+        }
+    }
+
+    fn sort_by_outputs<'a, F, I, T>(&'a self, f: F) -> Vec<T>
     where
         F: Fn((&'a String, &'a Vec<Workspace>)) -> I,
-        I: Iterator<Item = Element<'a, Message>>,
+        I: Iterator<Item = T>,
     {
         match self.output_order.is_empty() {
-            true => self
-                .workspaces
-                .iter()
-                .flat_map(f)
-                .collect::<Vec<Element<Message>>>(),
+            true => self.workspaces.iter().flat_map(f).collect::<Vec<T>>(),
             false => self
                 .output_order
                 .iter()
                 .filter_map(|o| self.workspaces.get_key_value(o))
                 .flat_map(f)
-                .collect::<Vec<Element<Message>>>(),
+                .collect::<Vec<T>>(),
         }
     }
 }
@@ -105,7 +143,7 @@ impl Module for NiriWorkspaceMod {
         anchor: &BarAnchor,
         _handlebars: &Handlebars,
     ) -> Element<'_, Message> {
-        list(
+        let workspaces = list(
             anchor,
             self.sort_by_outputs(|(output, workspaces)| {
                 workspaces.iter().map(|ws| {
@@ -127,10 +165,11 @@ impl Module for NiriWorkspaceMod {
                         ..Default::default()
                     };
                     let id = ws.id;
+                    // The background of the focused workspace is drawn by the highlight
                     if id == self.focused {
                         text = text.size(self.active_size).color(self.active_color);
-                        btn_style.background = self.active_background;
-                        btn_style.border = self.active_icon_border;
+                        btn_style.background = None;
+                        btn_style.border = Border::default();
                     }
                     container(
                         button(text)
@@ -153,12 +192,26 @@ impl Module for NiriWorkspaceMod {
             }),
         )
         .padding(self.cfg_override.padding.unwrap_or(config.padding))
-        .spacing(self.cfg_override.spacing.unwrap_or(config.spacing))
+        .spacing(self.cfg_override.spacing.unwrap_or(config.spacing));
+        Highlight::new(
+            workspaces,
+            self.highlight.value(),
+            self.active_background,
+            self.active_icon_border,
+        )
         .into()
     }
 
     impl_wrapper!();
 
+    fn set_animation(&mut self, config: &AnimationConfig) {
+        self.highlight.configure(config);
+    }
+
+    fn animating(&self) -> bool {
+        self.highlight.is_animating()
+    }
+
     fn requires(&self) -> Vec<TypeId> {
         vec![require_listener::<NiriListener>()]
     }
@@ -264,5 +317,23 @@ impl Module for NiriWorkspaceMod {
         });
     }
 
-    impl_on_click!();
+    impl_on_click!(default_action);
+
+    fn context(&self, item: Option<usize>) -> BTreeMap<&'static str, String> {
+        let mut ctx = BTreeMap::from([("active_id", self.focused.to_string())]);
+        // The workspace under the cursor
+        if let Some(ws) = item.and_then(|i| {
+            self.sort_by_outputs(|(_, workspaces)| workspaces.iter())
+                .get(i)
+                .copied()
+        }) {
+            ctx.extend([
+                ("id", ws.id.to_string()),
+                ("idx", ws.idx.to_string()),
+                ("name", ws.name.clone().unwrap_or_default()),
+                ("output", ws.output.clone().unwrap_or_default()),
+            ]);
+        }
+        ctx
+    }
 }
@@ -7,6 +7,7 @@ use iced::widget::{container, text};
 use iced::Element;
 
 use crate::config::popup_config::PopupConfig;
+use crate::helpers::Formats;
 use crate::{
     config::{
         anchor::BarAnchor,
@@ -24,6 +25,9 @@ pub struct TimeMod {
     cfg_override: ModuleConfigOverride,
     icon: String,
     fmt: String,
+    fmt_alt: Option<String>,
+    /// Only tracks which format is shown, the formats are strftime formats
+    formats: Formats,
 }
 
 impl Default for TimeMod {
@@ -32,6 +36,17 @@ impl Default for TimeMod {
             cfg_override: Default::default(),
             icon: "".to_string(),
             fmt: "%H:%M".to_string(),
+            fmt_alt: None,
+            formats: Formats::new("time"),
+        }
+    }
+}
+
+impl TimeMod {
+    fn current_format(&self) -> &str {
+        match (&self.fmt_alt, self.formats.is_alt()) {
+            (Some(alt), true) => alt,
+            _ => &self.fmt,
         }
     }
 }
@@ -61,7 +76,7 @@ impl Module for TimeMod {
             .fill(anchor)
             .padding(self.cfg_override.icon_margin.unwrap_or(config.icon_margin)),
             container(
-                text!("{}", time.format(&self.fmt))
+                text!("{}", time.format(self.current_format()))
                     .size(self.cfg_override.font_size.unwrap_or(config.font_size))
                     .color(self.cfg_override.text_color.unwrap_or(config.text_color))
                     .fill(anchor)
@@ -91,7 +106,13 @@ impl Module for TimeMod {
             .get("format")
             .and_then(|v| v.clone())
             .unwrap_or(default.fmt);
+        self.fmt_alt = config.get("format_alt").and_then(|v| v.clone());
+        self.formats.set_has_alt(self.fmt_alt.is_some());
     }
 
-    impl_on_click!();
+    impl_on_click!(formats);
+
+    fn next_format(&mut self) {
+        self.formats.next();
+    }
 }
@@ -0,0 +1,566 @@
+use std::{
+    any::Any,
+    collections::{BTreeMap, HashMap},
+    io::{self, ErrorKind},
+    sync::Arc,
+    time::Duration,
+};
+
+use bar_rs_derive::Builder;
+use handlebars::Handlebars;
+use iced::widget::{button::Style, column, container, row, scrollable, slider, Container, Text};
+use iced::{
+    futures::{channel::mpsc, SinkExt},
+    mouse, stream,
+    widget::text,
+    Alignment, Element, Subscription,
+};
+use pulse::{mask, volume_from_perc, DeviceInfo, Facility, PulseClient, StreamInfo};
+use tokio::{sync::broadcast, time::sleep};
+
+use crate::animation::{crossfade, Animated};
+use crate::button::button;
+use crate::config::animation_config::AnimationConfig;
+use crate::config::popup_config::{PopupConfig, PopupConfigOverride};
+use crate::helpers::{Formats, Thresholds};
+use crate::{
+    config::{
+        anchor::BarAnchor,
+        module_config::{LocalModuleConfig, ModuleConfigOverride},
+    },
+    fill::FillExt,
+    Message, NERD_FONT,
+};
+use crate::{impl_on_click, impl_popup_size, impl_wrapper};
+
+use super::{Action, ActionContext, Module, OnClickAction};
+
+mod pulse;
+
+#[derive(Debug, Builder)]
+pub struct VolumeMod {
+    mixer: Mixer,
+    /// The volume and icon which are shown, these follow the mixer smoothly
+    shown_level: Animated<f32>,
+    shown_icon: Animated<String>,
+    cfg_override: ModuleConfigOverride,
+    popup_cfg_override: PopupConfigOverride,
+    sender: broadcast::Sender<Arc<dyn Any + Send + Sync>>,
+    default_action: OnClickAction,
+    formats: Formats,
+    thresholds: Thresholds,
+}
+
+impl Default for VolumeMod {
+    fn default() -> Self {
+        Self {
+            mixer: Mixer::default(),
+            shown_level: Animated::default(),
+            shown_icon: Animated::new("󰖁".to_string()),
+            cfg_override: Default::default(),
+            popup_cfg_override: PopupConfigOverride {
+                width: Some(350),
+                height: Some(400),
+                ..Default::default()
+            },
+            sender: broadcast::channel(16).0,
+            default_action: default_action(5),
+            formats: Formats::new("volume").switch_on(mouse::Button::Right),
+            thresholds: Thresholds::new("volume"),
+        }
+    }
+}
+
+/// Scrolling changes the volume by `step` percent
+fn default_action(step: i16) -> OnClickAction {
+    OnClickAction {
+        scroll_up: Some(Box::new(VolumeAction::Step(step))),
+        scroll_down: Some(Box::new(VolumeAction::Step(-step))),
+        ..Default::default()
+    }
+}
+
+/// The state of the audio server, as far as we are interested in it
+#[derive(Debug, Default, Clone)]
+struct Mixer {
+    sinks: Vec<DeviceInfo>,
+    sources: Vec<DeviceInfo>,
+    streams: Vec<StreamInfo>,
+    default_sink: String,
+    default_source: String,
+}
+
+impl Mixer {
+    fn default_sink(&self) -> Option<&DeviceInfo> {
+        self.sinks.iter().find(|s| s.name == self.default_sink)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum VolumeAction {
+    ToggleMute,
+    /// Change the volume of the default sink by the given percentage
+    Step(i16),
+    SetDefaultSink(String),
+    SetSinkVolume(u32, Vec<u32>),
+    SetDefaultSource(String),
+    SetSourceMute(u32, bool),
+    SetStreamVolume(u32, Vec<u32>),
+    SetStreamMute(u32, bool),
+}
+
+impl VolumeAction {
+    fn send(self) -> Message {
+        Message::action(move |reg| {
+            reg.get_module::<VolumeMod>()
+                .sender
+                .send(Arc::new(self))
+                .ok();
+        })
+    }
+}
+
+impl Action for VolumeAction {
+    fn as_message(&self, _ctx: &ActionContext) -> Message {
+        self.clone().send()
+    }
+}
+
+impl VolumeMod {
+    fn set_mixer(&mut self, mixer: Mixer) {
+        self.mixer = mixer;
+        self.shown_level.set(self.level() as f32);
+        self.shown_icon.set(self.icon().to_string());
+    }
+
+    fn level(&self) -> u16 {
+        self.mixer
+            .default_sink()
+            .map(|sink| sink.volume_perc())
+            .unwrap_or_default()
+    }
+
+    fn icon(&self) -> &'static str {
+        match self.mixer.default_sink().map_or(true, |sink| sink.mute) {
+            true => "󰖁",
+            false => match self.level() {
+                n if n >= 50 => "󰕾",
+                n if n >= 25 => "󰖀",
+                _ => "󰕿",
+            },
+        }
+    }
+}
+
+impl Module for VolumeMod {
+    fn name(&self) -> String {
+        "volume".to_string()
+    }
+
+    fn view(
+        &self,
+        config: &LocalModuleConfig,
+        popup_config: &PopupConfig,
+        anchor: &BarAnchor,
+        handlebars: &Handlebars,
+    ) -> Element<'_, Message> {
+        let style = self.thresholds.style(&self.context(None));
+        let icon_color = style
+            .icon_color
+            .or(self.cfg_override.icon_color)
+            .unwrap_or(config.icon_color);
+        // The format shows the volume while it moves to the new level
+        let mut context = self.context(None);
+        context.insert("volume", self.shown_level.value().round().to_string());
+        button(
+            list![
+                anchor,
+                button(crossfade(&self.shown_icon, |icon, alpha| {
+                    text(icon.clone())
+                        .fill(anchor)
+                        .size(self.cfg_override.icon_size.unwrap_or(config.icon_size))
+                        .color(icon_color.scale_alpha(alpha))
+                        .font(NERD_FONT)
+                        .into()
+                }))
+                .style(|_, _| Style::default())
+                .on_event_try(|event, _, _, _, _| {
+                    event
+                        .is_left_click()
+                        .then(|| VolumeAction::ToggleMute.send())
+                })
+                .padding(self.cfg_override.icon_margin.unwrap_or(config.icon_margin)),
+                container(
+                    text(self.formats.render(handlebars, &context))
+                        .fill(anchor)
+                        .size(self.cfg_override.font_size.unwrap_or(config.font_size))
+                        .color(
+                            style
+                                .text_color
+                                .or(self.cfg_override.text_color)
+                                .unwrap_or(config.text_color)
+                        )
+                )
+                .padding(self.cfg_override.text_margin.unwrap_or(config.text_margin)),
+            ]
+            .spacing(self.cfg_override.spacing.unwrap_or(config.spacing)),
+        )
+        .on_event_try(Message::popup::<Self>(
+            self.popup_cfg_override.width.unwrap_or(popup_config.width),
+            self.popup_cfg_override
+                .height
+                .unwrap_or(popup_config.height),
+        ))
+        .style(|_, _| Style::default())
+        .into()
+    }
+
+    fn popup_view<'a>(
+        &'a self,
+        config: &'a PopupConfig,
+        _template: &Handlebars,
+    ) -> Element<'a, Message> {
+        let fmt_text = |text: Text<'a>| -> Container<'a, Message> {
+            container(
+                text.size(
+                    self.popup_cfg_override
+                        .font_size
+                        .unwrap_or(config.font_size),
+                )
+                .color(
+                    self.popup_cfg_override
+                        .text_color
+                        .unwrap_or(config.text_color),
+                ),
+            )
+            .padding(
+                self.popup_cfg_override
+                    .text_margin
+                    .unwrap_or(config.text_margin),
+            )
+        };
+        let icon = |icon: &'a str| -> Container<'a, Message> {
+            container(
+                text(icon)
+                    .font(NERD_FONT)
+                    .size(
+                        self.popup_cfg_override
+                            .icon_size
+                            .unwrap_or(config.icon_size),
+                    )
+                    .color(
+                        self.popup_cfg_override
+                            .icon_color
+                            .unwrap_or(config.icon_color),
+                    ),
+            )
+            .padding(
+                self.popup_cfg_override
+                    .icon_margin
+                    .unwrap_or(config.icon_margin),
+            )
+        };
+        let spacing = self.popup_cfg_override.spacing.unwrap_or(config.spacing);
+        // A device can be made the default by clicking on its name
+        let device =
+            |dev: &'a DeviceInfo, is_default: bool, on_select: Message| -> Element<'a, Message> {
+                button(
+                    row![
+                        icon(match is_default {
+                            true => "󰄴",
+                            false => "󰝦",
+                        }),
+                        fmt_text(text(&dev.description)),
+                    ]
+                    .spacing(spacing)
+                    .align_y(Alignment::Center),
+                )
+                .on_event(on_select)
+                .style(|_, _| Style::default())
+                .into()
+            };
+
+        let sinks = self.mixer.sinks.iter().map(|sink| {
+            device(
+                sink,
+                sink.name == self.mixer.default_sink,
+                VolumeAction::SetDefaultSink(sink.name.clone()).send(),
+            )
+        });
+        let sources = self
+            .mixer
+            .sources
+            .iter()
+            .map(|source| -> Element<'a, Message> {
+                row![
+                    button(icon(match source.mute {
+                        true => "󰍭",
+                        false => "󰍬",
+                    }))
+                    .on_event(VolumeAction::SetSourceMute(source.index, !source.mute).send())
+                    .style(|_, _| Style::default()),
+                    device(
+                        source,
+                        source.name == self.mixer.default_source,
+                        VolumeAction::SetDefaultSource(source.name.clone()).send(),
+                    ),
+                ]
+                .spacing(spacing)
+                .align_y(Alignment::Center)
+                .into()
+            });
+        let streams = self
+            .mixer
+            .streams
+            .iter()
+            .map(|stream| -> Element<'a, Message> {
+                let (index, channels) = (stream.index, stream.volume.len());
+                column![
+                    row![
+                        button(icon(match stream.mute {
+                            true => "󰖁",
+                            false => "󰕾",
+                        }))
+                        .on_event(VolumeAction::SetStreamMute(index, !stream.mute).send())
+                        .style(|_, _| Style::default()),
+                        fmt_text(text!("{} ({}%)", stream.name, stream.volume_perc())),
+                    ]
+                    .spacing(spacing)
+                    .align_y(Alignment::Center),
+                    slider(0.0..=100.0, stream.volume_perc() as f32, move |perc| {
+                        VolumeAction::SetStreamVolume(
+                            index,
+                            volume_from_perc(channels, perc as u16),
+                        )
+                        .send()
+                    }),
+                ]
+                .spacing(spacing)
+                .into()
+            });
+        // The volume of the default sink
+        let volume = self.mixer.default_sink().map(|sink| {
+            let (index, channels) = (sink.index, sink.volume.len());
+            slider(0.0..=100.0, sink.volume_perc() as f32, move |perc| {
+                VolumeAction::SetSinkVolume(index, volume_from_perc(channels, perc as u16)).send()
+            })
+        });
+        let streams: Element<Message> = match self.mixer.streams.is_empty() {
+            true => fmt_text(text("No application is playing audio")).into(),
+            false => column(streams).spacing(spacing).into(),
+        };
+
+        container(scrollable(
+            column![
+                fmt_text(text("Output devices")),
+                column(sinks).spacing(spacing),
+            ]
+            .push_maybe(volume)
+            .push(fmt_text(text("Input devices")))
+            .push(column(sources).spacing(spacing))
+            .push(fmt_text(text("Applications")))
+            .push(streams)
+            .spacing(spacing),
+        ))
+        .padding(self.popup_cfg_override.padding.unwrap_or(config.padding))
+        .style(|_| container::Style {
+            background: Some(
+                self.popup_cfg_override
+                    .background
+                    .unwrap_or(config.background),
+            ),
+            border: self.popup_cfg_override.border.unwrap_or(config.border),
+            ..Default::default()
+        })
+        .fill_maybe(
+            self.popup_cfg_override
+                .fill_content_to_size
+                .unwrap_or(config.fill_content_to_size),
+        )
+        .into()
+    }
+
+    impl_wrapper!(thresholds);
+
+    fn read_config(
+        &mut self,
+        config: &HashMap<String, Option<String>>,
+        popup_config: &HashMap<String, Option<String>>,
+        templates: &mut Handlebars,
+    ) {
+        self.cfg_override = config.into();
+        self.thresholds.read_config(config);
+        self.popup_cfg_override.update(popup_config);
+        self.formats.read_config(config, "{{volume}}%", templates);
+        self.default_action = default_action(
+            config
+                .get("step")
+                .and_then(|v| v.as_ref().and_then(|v| v.parse().ok()))
+                .unwrap_or(5),
+        );
+    }
+
+    impl_on_click!(default_action, formats);
+
+    fn set_animation(&mut self, config: &AnimationConfig) {
+        self.shown_level.configure(config);
+        self.shown_icon.configure(config);
+    }
+
+    fn animating(&self) -> bool {
+        self.shown_level.is_animating() || self.shown_icon.is_animating()
+    }
+
+    fn next_format(&mut self) {
+        self.formats.next();
+    }
+
+    impl_popup_size!();
+
+    fn context(&self, _item: Option<usize>) -> BTreeMap<&'static str, String> {
+        let sink = self.mixer.default_sink();
+        let mut ctx = BTreeMap::from([
+            ("volume", self.level().to_string()),
+            ("muted", sink.map_or(true, |sink| sink.mute).to_string()),
+            (
+                "sink",
+                sink.map(|sink| sink.name.clone()).unwrap_or_default(),
+            ),
+            (
+                "sink_description",
+                sink.map(|sink| sink.description.clone())
+                    .unwrap_or_default(),
+            ),
+        ]);
+        ctx.insert("state", self.thresholds.state(&ctx).to_string());
+        ctx
+    }
+
+    fn subscription(&self) -> Option<iced::Subscription<Message>> {
+        Some(Subscription::run(|| {
+            stream::channel(1, |mut sender| async move {
+                let (sx, mut rx) = tokio::sync::mpsc::channel(1);
+                sender
+                    .send(Message::GetReceiver(sx, |reg| {
+                        reg.get_module::<VolumeMod>().sender.subscribe()
+                    }))
+                    .await
+                    .unwrap_or_else(|err| {
+                        eprintln!("Trying to request a receiver for volume actions failed with err: {err}");
+                    });
+                let Some(mut receiver) = rx.recv().await else {
+                    return;
+                };
+                drop(rx);
+
+                // Reconnect if the server goes away, e.g. when pipewire is restarted
+                loop {
+                    if let Err(e) = monitor(&mut sender, &mut receiver).await {
+                        eprintln!("Lost connection to the PulseAudio server: {e}");
+                    }
+                    sleep(Duration::from_secs(5)).await;
+                }
+            })
+        }))
+    }
+}
+
+/// Track the devices and streams of the audio server and execute the actions sent by [VolumeMod]
+async fn monitor(
+    sender: &mut mpsc::Sender<Message>,
+    receiver: &mut broadcast::Receiver<Arc<dyn Any + Send + Sync>>,
+) -> io::Result<()> {
+    let mut client = PulseClient::connect().await?;
+    client
+        .subscribe(mask::SINK | mask::SOURCE | mask::SINK_INPUT | mask::SERVER)
+        .await?;
+    let server = client.server_info().await?;
+    let mut mixer = Mixer {
+        sinks: client.sinks().await?,
+        sources: client.sources().await?,
+        streams: client.sink_inputs().await?,
+        default_sink: server.default_sink,
+        default_source: server.default_source,
+    };
+    send_mixer(sender, &mixer).await;
+
+    loop {
+        tokio::select! {
+            event = client.next_event() => {
+                match event?.facility {
+                    // The default sink or source might have changed
+                    Facility::Server => {
+                        let server = client.server_info().await?;
+                        mixer.default_sink = server.default_sink;
+                        mixer.default_source = server.default_source;
+                    }
+                    Facility::Sink => mixer.sinks = client.sinks().await?,
+                    Facility::Source => mixer.sources = client.sources().await?,
+                    Facility::SinkInput => mixer.streams = client.sink_inputs().await?,
+                    _ => continue,
+                }
+                send_mixer(sender, &mixer).await;
+            }
+            Ok(action) = receiver.recv() => {
+                let Some(action) = action.downcast_ref::<VolumeAction>() else {
+                    continue;
+                };
+                let result = match action {
+                    VolumeAction::ToggleMute => match mixer.default_sink() {
+                        Some(sink) => client.set_sink_mute(sink.index, !sink.mute).await,
+                        None => Ok(()),
+                    },
+                    VolumeAction::Step(step) => {
+                        let sink = mixer.sinks.iter_mut().find(|s| s.name == mixer.default_sink);
+                        match sink {
+                            Some(sink) => {
+                                let current = sink.volume_perc();
+                                // Don't raise the volume above 100%, but keep it if it is higher
+                                let perc = current
+                                    .saturating_add_signed(*step)
+                                    .min(current.max(100));
+                                // Remember the new volume, so that fast scrolling adds up
+                                sink.volume = volume_from_perc(sink.volume.len(), perc);
+                                client.set_sink_volume(sink.index, &sink.volume).await
+                            }
+                            None => Ok(()),
+                        }
+                    }
+                    VolumeAction::SetDefaultSink(name) => client.set_default_sink(name).await,
+                    VolumeAction::SetSinkVolume(index, volume) => {
+                        client.set_sink_volume(*index, volume).await
+                    }
+                    VolumeAction::SetDefaultSource(name) => client.set_default_source(name).await,
+                    VolumeAction::SetSourceMute(index, mute) => {
+                        client.set_source_mute(*index, *mute).await
+                    }
+                    VolumeAction::SetStreamVolume(index, volume) => {
+                        client.set_sink_input_volume(*index, volume).await
+                    }
+                    VolumeAction::SetStreamMute(index, mute) => {
+                        client.set_sink_input_mute(*index, *mute).await
+                    }
+                };
+                match result {
+                    // The server rejected the request, e.g. because the stream is gone already
+                    Err(e) if e.kind() == ErrorKind::Other => {
+                        eprintln!("Failed to execute volume action {action:?}: {e}")
+                    }
+                    result => result?,
+                }
+            }
+        }
+    }
+}
+
+async fn send_mixer(sender: &mut mpsc::Sender<Message>, mixer: &Mixer) {
+    let mixer = mixer.clone();
+    sender
+        .send(Message::update(move |reg| {
+            reg.get_module_mut::<VolumeMod>().set_mixer(mixer)
+        }))
+        .await
+        .unwrap_or_else(|err| {
+            eprintln!("Trying to send volume failed with err: {err}");
+        });
+}
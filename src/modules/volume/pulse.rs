@@ -0,0 +1,718 @@
+//! A minimal client for the PulseAudio native protocol, which is also spoken by pipewire-pulse.
+//!
+//! Only the few commands needed by the volume module are implemented. The command ids and the
+//! tagstruct layout are defined in `src/pulsecore/native-common.h` and `src/pulsecore/tagstruct.h`
+//! of the PulseAudio sources, the content of the replies in `src/pulse/introspect.c`.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    env, fs,
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{
+        unix::{OwnedReadHalf, OwnedWriteHalf},
+        UnixStream,
+    },
+    sync::mpsc,
+};
+
+/// The protocol version we announce. The server will use the minimum of its own and this version,
+/// which defines the layout of all replies.
+const PROTOCOL_VERSION: u32 = 32;
+const CONTROL_CHANNEL: u32 = u32::MAX;
+const INVALID_INDEX: u32 = u32::MAX;
+const COOKIE_LENGTH: usize = 256;
+/// Packets bigger than this are certainly not meant for us
+const MAX_PACKET_SIZE: usize = 16 * 1024 * 1024;
+/// The volume representing 100%
+pub const VOLUME_NORM: u32 = 0x10000;
+
+mod command {
+    pub const ERROR: u32 = 0;
+    pub const REPLY: u32 = 2;
+    pub const AUTH: u32 = 8;
+    pub const SET_CLIENT_NAME: u32 = 9;
+    pub const GET_SERVER_INFO: u32 = 20;
+    pub const GET_SINK_INFO_LIST: u32 = 22;
+    pub const GET_SOURCE_INFO_LIST: u32 = 24;
+    pub const GET_SINK_INPUT_INFO_LIST: u32 = 30;
+    pub const SUBSCRIBE: u32 = 35;
+    pub const SET_SINK_VOLUME: u32 = 36;
+    pub const SET_SINK_INPUT_VOLUME: u32 = 37;
+    pub const SET_SINK_MUTE: u32 = 39;
+    pub const SET_SOURCE_MUTE: u32 = 40;
+    pub const SET_DEFAULT_SINK: u32 = 44;
+    pub const SET_DEFAULT_SOURCE: u32 = 45;
+    pub const SUBSCRIBE_EVENT: u32 = 66;
+    pub const SET_SINK_INPUT_MUTE: u32 = 69;
+}
+
+mod tag {
+    pub const STRING: u8 = b't';
+    pub const STRING_NULL: u8 = b'N';
+    pub const U32: u8 = b'L';
+    pub const U8: u8 = b'B';
+    pub const SAMPLE_SPEC: u8 = b'a';
+    pub const ARBITRARY: u8 = b'x';
+    pub const BOOLEAN_TRUE: u8 = b'1';
+    pub const BOOLEAN_FALSE: u8 = b'0';
+    pub const USEC: u8 = b'U';
+    pub const CHANNEL_MAP: u8 = b'm';
+    pub const CVOLUME: u8 = b'v';
+    pub const PROPLIST: u8 = b'P';
+    pub const VOLUME: u8 = b'V';
+    pub const FORMAT_INFO: u8 = b'f';
+}
+
+/// Bitmask of the facilities to receive events for, see `pa_subscription_mask_t`
+pub mod mask {
+    pub const SINK: u32 = 0x0001;
+    pub const SOURCE: u32 = 0x0002;
+    pub const SINK_INPUT: u32 = 0x0004;
+    pub const SERVER: u32 = 0x0080;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Facility {
+    Sink,
+    Source,
+    SinkInput,
+    SourceOutput,
+    Server,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SubscriptionEvent {
+    pub facility: Facility,
+    pub index: u32,
+}
+
+impl From<(u32, u32)> for SubscriptionEvent {
+    fn from((event, index): (u32, u32)) -> Self {
+        Self {
+            facility: match event & 0x0F {
+                0 => Facility::Sink,
+                1 => Facility::Source,
+                2 => Facility::SinkInput,
+                3 => Facility::SourceOutput,
+                7 => Facility::Server,
+                _ => Facility::Other,
+            },
+            index,
+        }
+    }
+}
+
+/// A sink (output) or source (input) device
+#[derive(Debug, Clone, Default)]
+pub struct DeviceInfo {
+    pub index: u32,
+    pub name: String,
+    pub description: String,
+    pub volume: Vec<u32>,
+    pub mute: bool,
+}
+
+impl DeviceInfo {
+    /// The average volume of all channels in percent
+    pub fn volume_perc(&self) -> u16 {
+        volume_perc(&self.volume)
+    }
+}
+
+/// A playback stream of an application
+#[derive(Debug, Clone, Default)]
+pub struct StreamInfo {
+    pub index: u32,
+    pub name: String,
+    pub volume: Vec<u32>,
+    pub mute: bool,
+}
+
+impl StreamInfo {
+    /// The average volume of all channels in percent
+    pub fn volume_perc(&self) -> u16 {
+        volume_perc(&self.volume)
+    }
+}
+
+pub fn volume_perc(volume: &[u32]) -> u16 {
+    match volume.len() {
+        0 => 0,
+        n => {
+            let avg = volume.iter().map(|v| *v as u64).sum::<u64>() / n as u64;
+            ((avg * 100) as f32 / VOLUME_NORM as f32).round() as u16
+        }
+    }
+}
+
+/// Set all of the given number of channels to the given volume in percent
+pub fn volume_from_perc(channels: usize, perc: u16) -> Vec<u32> {
+    vec![(perc as u64 * VOLUME_NORM as u64 / 100) as u32; channels.max(1)]
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ServerInfo {
+    pub default_sink: String,
+    pub default_source: String,
+}
+
+pub struct PulseClient {
+    writer: OwnedWriteHalf,
+    packets: mpsc::UnboundedReceiver<io::Result<Vec<u8>>>,
+    events: VecDeque<SubscriptionEvent>,
+    tag: u32,
+}
+
+impl PulseClient {
+    /// Connect to the server and authenticate.
+    pub async fn connect() -> io::Result<Self> {
+        let socket = UnixStream::connect(socket_path()?).await?;
+        let (reader, writer) = socket.into_split();
+        let (sx, packets) = mpsc::unbounded_channel();
+        tokio::spawn(read_packets(reader, sx));
+
+        let mut client = Self {
+            writer,
+            packets,
+            events: VecDeque::new(),
+            tag: 0,
+        };
+        client
+            .request(
+                command::AUTH,
+                TagStruct::default()
+                    .u32(PROTOCOL_VERSION)
+                    .arbitrary(&read_cookie()),
+            )
+            .await?;
+        client
+            .request(
+                command::SET_CLIENT_NAME,
+                TagStruct::default().proplist(&[("application.name", "bar-rs")]),
+            )
+            .await?;
+        Ok(client)
+    }
+
+    pub async fn subscribe(&mut self, mask: u32) -> io::Result<()> {
+        self.request(command::SUBSCRIBE, TagStruct::default().u32(mask))
+            .await
+            .map(|_| ())
+    }
+
+    /// Wait for the next subscription event. This is cancel safe.
+    pub async fn next_event(&mut self) -> io::Result<SubscriptionEvent> {
+        if let Some(event) = self.events.pop_front() {
+            return Ok(event);
+        }
+        loop {
+            let mut reader = self.next_packet().await?;
+            if reader.u32()? == command::SUBSCRIBE_EVENT {
+                reader.u32()?;
+                return Ok((reader.u32()?, reader.u32()?).into());
+            }
+        }
+    }
+
+    pub async fn server_info(&mut self) -> io::Result<ServerInfo> {
+        let mut reply = self
+            .request(command::GET_SERVER_INFO, TagStruct::default())
+            .await?;
+        // user name, host name, server version, server name
+        for _ in 0..4 {
+            reply.string()?;
+        }
+        reply.sample_spec()?;
+        Ok(ServerInfo {
+            default_sink: reply.string()?.unwrap_or_default(),
+            default_source: reply.string()?.unwrap_or_default(),
+        })
+    }
+
+    pub async fn sinks(&mut self) -> io::Result<Vec<DeviceInfo>> {
+        let mut reply = self
+            .request(command::GET_SINK_INFO_LIST, TagStruct::default())
+            .await?;
+        let mut sinks = vec![];
+        while !reply.is_empty() {
+            sinks.push(reply.device_info()?.0);
+        }
+        Ok(sinks)
+    }
+
+    /// All sources, except for the monitors of sinks
+    pub async fn sources(&mut self) -> io::Result<Vec<DeviceInfo>> {
+        let mut reply = self
+            .request(command::GET_SOURCE_INFO_LIST, TagStruct::default())
+            .await?;
+        let mut sources = vec![];
+        while !reply.is_empty() {
+            let (source, monitor_of_sink) = reply.device_info()?;
+            if monitor_of_sink == INVALID_INDEX {
+                sources.push(source);
+            }
+        }
+        Ok(sources)
+    }
+
+    pub async fn sink_inputs(&mut self) -> io::Result<Vec<StreamInfo>> {
+        let mut reply = self
+            .request(command::GET_SINK_INPUT_INFO_LIST, TagStruct::default())
+            .await?;
+        let mut streams = vec![];
+        while !reply.is_empty() {
+            streams.push(reply.stream_info()?);
+        }
+        Ok(streams)
+    }
+
+    pub async fn set_default_sink(&mut self, name: &str) -> io::Result<()> {
+        self.request(
+            command::SET_DEFAULT_SINK,
+            TagStruct::default().string(Some(name)),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    pub async fn set_default_source(&mut self, name: &str) -> io::Result<()> {
+        self.request(
+            command::SET_DEFAULT_SOURCE,
+            TagStruct::default().string(Some(name)),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    pub async fn set_sink_volume(&mut self, index: u32, volume: &[u32]) -> io::Result<()> {
+        self.request(
+            command::SET_SINK_VOLUME,
+            TagStruct::default().u32(index).string(None).cvolume(volume),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    pub async fn set_sink_input_volume(&mut self, index: u32, volume: &[u32]) -> io::Result<()> {
+        self.request(
+            command::SET_SINK_INPUT_VOLUME,
+            TagStruct::default().u32(index).cvolume(volume),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    pub async fn set_sink_mute(&mut self, index: u32, mute: bool) -> io::Result<()> {
+        self.request(
+            command::SET_SINK_MUTE,
+            TagStruct::default().u32(index).string(None).bool(mute),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    pub async fn set_source_mute(&mut self, index: u32, mute: bool) -> io::Result<()> {
+        self.request(
+            command::SET_SOURCE_MUTE,
+            TagStruct::default().u32(index).string(None).bool(mute),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    pub async fn set_sink_input_mute(&mut self, index: u32, mute: bool) -> io::Result<()> {
+        self.request(
+            command::SET_SINK_INPUT_MUTE,
+            TagStruct::default().u32(index).bool(mute),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn request(&mut self, cmd: u32, data: TagStruct) -> io::Result<TagReader> {
+        let tag = self.tag;
+        // u32::MAX is reserved for packets which are not a reply
+        self.tag = (self.tag + 1) % (u32::MAX - 1);
+        let payload = [TagStruct::default().u32(cmd).u32(tag).0, data.0].concat();
+        let mut packet = Vec::with_capacity(20 + payload.len());
+        for field in [payload.len() as u32, CONTROL_CHANNEL, 0, 0, 0] {
+            packet.extend(field.to_be_bytes());
+        }
+        packet.extend(payload);
+        self.writer.write_all(&packet).await?;
+
+        loop {
+            let mut reader = self.next_packet().await?;
+            match (reader.u32()?, reader.u32()?) {
+                (command::REPLY, reply_tag) if reply_tag == tag => return Ok(reader),
+                (command::ERROR, reply_tag) if reply_tag == tag => {
+                    return Err(io::Error::other(format!(
+                        "Server replied with error code {}",
+                        reader.u32()?
+                    )))
+                }
+                (command::SUBSCRIBE_EVENT, _) => {
+                    let event = (reader.u32()?, reader.u32()?).into();
+                    self.events.push_back(event);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    async fn next_packet(&mut self) -> io::Result<TagReader> {
+        self.packets
+            .recv()
+            .await
+            .unwrap_or_else(|| Err(ErrorKind::UnexpectedEof.into()))
+            .map(|data| TagReader { data, pos: 0 })
+    }
+}
+
+async fn read_packets(
+    mut reader: OwnedReadHalf,
+    sender: mpsc::UnboundedSender<io::Result<Vec<u8>>>,
+) {
+    loop {
+        let packet = read_packet(&mut reader).await;
+        let failed = packet.is_err();
+        if sender.send(packet).is_err() || failed {
+            return;
+        }
+    }
+}
+
+async fn read_packet(reader: &mut OwnedReadHalf) -> io::Result<Vec<u8>> {
+    loop {
+        // length, channel, offset (high and low bits), flags
+        let mut descriptor = [0; 20];
+        reader.read_exact(&mut descriptor).await?;
+        let length = u32::from_be_bytes(descriptor[0..4].try_into().unwrap()) as usize;
+        let channel = u32::from_be_bytes(descriptor[4..8].try_into().unwrap());
+        if length > MAX_PACKET_SIZE {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Received a packet of {length} bytes"),
+            ));
+        }
+        let mut payload = vec![0; length];
+        reader.read_exact(&mut payload).await?;
+        // Memory blocks of audio streams are sent on other channels
+        if channel == CONTROL_CHANNEL {
+            return Ok(payload);
+        }
+    }
+}
+
+fn socket_path() -> io::Result<PathBuf> {
+    if let Some(path) = env::var("PULSE_SERVER").ok().and_then(|servers| {
+        servers
+            .split_whitespace()
+            .find_map(|s| s.strip_prefix("unix:").map(PathBuf::from))
+    }) {
+        return Ok(path);
+    }
+    env::var("XDG_RUNTIME_DIR")
+        .map(|dir| PathBuf::from(dir).join("pulse/native"))
+        .map_err(|_| io::Error::new(ErrorKind::NotFound, "XDG_RUNTIME_DIR is not set"))
+}
+
+/// PulseAudio authenticates clients with a cookie, pipewire-pulse ignores it.
+fn read_cookie() -> Vec<u8> {
+    let home = env::var("HOME").unwrap_or_default();
+    let candidates = [
+        env::var("PULSE_COOKIE").ok(),
+        env::var("XDG_CONFIG_HOME")
+            .ok()
+            .map(|dir| format!("{dir}/pulse/cookie")),
+        Some(format!("{home}/.config/pulse/cookie")),
+        Some(format!("{home}/.pulse-cookie")),
+    ];
+    for path in candidates.into_iter().flatten() {
+        if let Ok(cookie) = fs::read(&path) {
+            if cookie.len() == COOKIE_LENGTH {
+                return cookie;
+            }
+        }
+    }
+    vec![0; COOKIE_LENGTH]
+}
+
+#[derive(Default)]
+struct TagStruct(Vec<u8>);
+
+impl TagStruct {
+    fn u32(mut self, value: u32) -> Self {
+        self.0.push(tag::U32);
+        self.0.extend(value.to_be_bytes());
+        self
+    }
+
+    fn bool(mut self, value: bool) -> Self {
+        self.0.push(match value {
+            true => tag::BOOLEAN_TRUE,
+            false => tag::BOOLEAN_FALSE,
+        });
+        self
+    }
+
+    fn string(mut self, value: Option<&str>) -> Self {
+        match value {
+            Some(value) => {
+                self.0.push(tag::STRING);
+                self.0.extend(value.as_bytes());
+                self.0.push(0);
+            }
+            None => self.0.push(tag::STRING_NULL),
+        }
+        self
+    }
+
+    fn cvolume(mut self, volume: &[u32]) -> Self {
+        self.0.push(tag::CVOLUME);
+        self.0.push(volume.len() as u8);
+        for channel in volume {
+            self.0.extend(channel.to_be_bytes());
+        }
+        self
+    }
+
+    fn arbitrary(mut self, value: &[u8]) -> Self {
+        self.0.push(tag::ARBITRARY);
+        self.0.extend((value.len() as u32).to_be_bytes());
+        self.0.extend(value);
+        self
+    }
+
+    fn proplist(mut self, props: &[(&str, &str)]) -> Self {
+        self.0.push(tag::PROPLIST);
+        for (key, value) in props {
+            // Values are stored including their NUL terminator
+            let value = [value.as_bytes(), &[0]].concat();
+            self = self
+                .string(Some(key))
+                .u32(value.len() as u32)
+                .arbitrary(&value);
+        }
+        self.string(None)
+    }
+}
+
+struct TagReader {
+    data: Vec<u8>,
+    pos: usize,
+}
+
+impl TagReader {
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    fn take(&mut self, n: usize) -> io::Result<&[u8]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + n)
+            .ok_or(io::Error::from(ErrorKind::UnexpectedEof))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn expect(&mut self, expected: u8) -> io::Result<()> {
+        match self.take(1)?[0] {
+            t if t == expected => Ok(()),
+            t => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Expected tag '{}', got '{}'", expected as char, t as char),
+            )),
+        }
+    }
+
+    fn raw_u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn raw_u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        self.expect(tag::U32)?;
+        self.raw_u32()
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        self.expect(tag::U8)?;
+        Ok(self.take(1)?[0])
+    }
+
+    fn usec(&mut self) -> io::Result<u64> {
+        self.expect(tag::USEC)?;
+        self.raw_u64()
+    }
+
+    fn volume(&mut self) -> io::Result<u32> {
+        self.expect(tag::VOLUME)?;
+        self.raw_u32()
+    }
+
+    fn bool(&mut self) -> io::Result<bool> {
+        match self.take(1)?[0] {
+            tag::BOOLEAN_TRUE => Ok(true),
+            tag::BOOLEAN_FALSE => Ok(false),
+            t => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Expected a boolean, got '{}'", t as char),
+            )),
+        }
+    }
+
+    fn string(&mut self) -> io::Result<Option<String>> {
+        match self.take(1)?[0] {
+            tag::STRING_NULL => Ok(None),
+            tag::STRING => {
+                let len = self.data[self.pos..]
+                    .iter()
+                    .position(|b| *b == 0)
+                    .ok_or(io::Error::from(ErrorKind::UnexpectedEof))?;
+                let value = String::from_utf8_lossy(self.take(len)?).to_string();
+                self.pos += 1;
+                Ok(Some(value))
+            }
+            t => Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Expected a string, got '{}'", t as char),
+            )),
+        }
+    }
+
+    fn arbitrary(&mut self) -> io::Result<&[u8]> {
+        self.expect(tag::ARBITRARY)?;
+        let len = self.raw_u32()? as usize;
+        self.take(len)
+    }
+
+    fn sample_spec(&mut self) -> io::Result<()> {
+        self.expect(tag::SAMPLE_SPEC)?;
+        // format, channels, rate
+        self.take(6).map(|_| ())
+    }
+
+    fn channel_map(&mut self) -> io::Result<()> {
+        self.expect(tag::CHANNEL_MAP)?;
+        let channels = self.take(1)?[0] as usize;
+        self.take(channels).map(|_| ())
+    }
+
+    fn cvolume(&mut self) -> io::Result<Vec<u32>> {
+        self.expect(tag::CVOLUME)?;
+        let channels = self.take(1)?[0];
+        (0..channels).map(|_| self.raw_u32()).collect()
+    }
+
+    fn proplist(&mut self) -> io::Result<HashMap<String, String>> {
+        self.expect(tag::PROPLIST)?;
+        let mut props = HashMap::new();
+        while let Some(key) = self.string()? {
+            self.u32()?;
+            let value = String::from_utf8_lossy(self.arbitrary()?)
+                .trim_end_matches('\0')
+                .to_string();
+            props.insert(key, value);
+        }
+        Ok(props)
+    }
+
+    fn format_info(&mut self) -> io::Result<()> {
+        self.expect(tag::FORMAT_INFO)?;
+        // encoding
+        self.u8()?;
+        self.proplist().map(|_| ())
+    }
+
+    /// Read a sink or source, both share the same layout. Also returns the index of the monitor
+    /// source of a sink, or of the sink monitored by a source.
+    fn device_info(&mut self) -> io::Result<(DeviceInfo, u32)> {
+        let index = self.u32()?;
+        let name = self.string()?.unwrap_or_default();
+        let description = self.string()?.unwrap_or_default();
+        self.sample_spec()?;
+        self.channel_map()?;
+        // owner module
+        self.u32()?;
+        let volume = self.cvolume()?;
+        let mute = self.bool()?;
+        let monitor = self.u32()?;
+        // monitor name, latency, driver, flags
+        self.string()?;
+        self.usec()?;
+        self.string()?;
+        self.u32()?;
+        self.proplist()?;
+        // configured latency, base volume, state, volume steps, card
+        self.usec()?;
+        self.volume()?;
+        self.u32()?;
+        self.u32()?;
+        self.u32()?;
+        let ports = self.u32()?;
+        for _ in 0..ports {
+            // name, description, priority, availability
+            self.string()?;
+            self.string()?;
+            self.u32()?;
+            self.u32()?;
+        }
+        // active port
+        self.string()?;
+        let formats = self.u8()?;
+        for _ in 0..formats {
+            self.format_info()?;
+        }
+        Ok((
+            DeviceInfo {
+                index,
+                name,
+                description,
+                volume,
+                mute,
+            },
+            monitor,
+        ))
+    }
+
+    fn stream_info(&mut self) -> io::Result<StreamInfo> {
+        let index = self.u32()?;
+        let name = self.string()?.unwrap_or_default();
+        // owner module, client, sink
+        self.u32()?;
+        self.u32()?;
+        self.u32()?;
+        self.sample_spec()?;
+        self.channel_map()?;
+        let volume = self.cvolume()?;
+        // buffer latency, sink latency, resample method, driver
+        self.usec()?;
+        self.usec()?;
+        self.string()?;
+        self.string()?;
+        let mute = self.bool()?;
+        let props = self.proplist()?;
+        // corked, has volume, volume writable
+        self.bool()?;
+        self.bool()?;
+        self.bool()?;
+        self.format_info()?;
+        Ok(StreamInfo {
+            index,
+            name: props.get("application.name").cloned().unwrap_or(name),
+            volume,
+            mute,
+        })
+    }
+}
@@ -1,11 +1,17 @@
-use std::{any::TypeId, collections::HashMap};
+use std::{
+    any::TypeId,
+    collections::{BTreeMap, HashMap},
+};
 
 use bar_rs_derive::Builder;
 use handlebars::Handlebars;
-use iced::widget::{container, rich_text, span, text};
+use iced::widget::{container, text};
 use iced::Element;
 
 use crate::config::popup_config::PopupConfig;
+use crate::helpers::Formats;
+use crate::marquee::trim;
+use crate::rotate::bar_text;
 use crate::tooltip::ElementExt;
 use crate::{
     config::{
@@ -22,34 +28,30 @@ use crate::{impl_on_click, impl_wrapper};
 #[derive(Debug, Builder)]
 pub struct WayfireWindowMod {
     pub title: Option<String>,
+    /// Whether any view is focused
+    pub focused: bool,
+    /// Whether the focused view is fullscreen
+    pub fullscreen: bool,
+    pub floating: bool,
     max_length: usize,
     cfg_override: ModuleConfigOverride,
+    formats: Formats,
 }
 
 impl Default for WayfireWindowMod {
     fn default() -> Self {
         Self {
             title: None,
+            focused: false,
+            fullscreen: false,
+            floating: false,
             max_length: 25,
             cfg_override: Default::default(),
+            formats: Formats::new("wayfire.window"),
         }
     }
 }
 
-impl WayfireWindowMod {
-    pub fn get_title(&self) -> Option<String> {
-        self.title
-            .as_ref()
-            .map(|title| match title.len() > self.max_length {
-                true => format!(
-                    "{}...",
-                    title.chars().take(self.max_length - 3).collect::<String>()
-                ),
-                false => title.to_string(),
-            })
-    }
-}
-
 impl Module for WayfireWindowMod {
     fn name(&self) -> String {
         "wayfire.window".to_string()
@@ -64,18 +66,27 @@ impl Module for WayfireWindowMod {
         config: &LocalModuleConfig,
         _popup_config: &PopupConfig,
         anchor: &BarAnchor,
-        _handlebars: &Handlebars,
+        handlebars: &Handlebars,
     ) -> Element<'_, Message> {
-        container(
-            rich_text([span(self.get_title().unwrap_or_default())
-                .size(self.cfg_override.font_size.unwrap_or(config.font_size))
-                .color(self.cfg_override.text_color.unwrap_or(config.text_color))])
-            .fill(anchor),
-        )
+        let title = self.formats.render(handlebars, &self.context(None));
+        let marquee = self.cfg_override.marquee.unwrap_or(config.marquee);
+        container(bar_text(
+            match marquee.enabled {
+                true => title.clone(),
+                false => trim(&title, self.max_length),
+            },
+            self.cfg_override.font_size.unwrap_or(config.font_size),
+            self.cfg_override.text_color.unwrap_or(config.text_color),
+            anchor,
+            self.cfg_override.rotate_text.unwrap_or(config.rotate_text),
+            marquee,
+        ))
         .padding(self.cfg_override.text_margin.unwrap_or(config.text_margin))
         .tooltip_maybe(
-            self.get_title()
-                .and_then(|t| (t.len() > self.max_length).then_some(text(t).size(12))),
+            (title.chars().count() > self.max_length)
+                .then(|| text(title).size(config.tooltip.font_size)),
+            &config.tooltip,
+            anchor,
         )
     }
 
@@ -89,14 +100,28 @@ impl Module for WayfireWindowMod {
         &mut self,
         config: &HashMap<String, Option<String>>,
         _popup_config: &HashMap<String, Option<String>>,
-        _templates: &mut Handlebars,
+        templates: &mut Handlebars,
     ) {
         self.cfg_override = config.into();
+        self.formats.read_config(config, "{{title}}", templates);
         self.max_length = config
             .get("max_length")
             .and_then(|v| v.as_ref().and_then(|v| v.parse().ok()))
             .unwrap_or(Self::default().max_length);
     }
 
-    impl_on_click!();
+    impl_on_click!(formats);
+
+    fn next_format(&mut self) {
+        self.formats.next();
+    }
+
+    fn context(&self, _item: Option<usize>) -> BTreeMap<&'static str, String> {
+        BTreeMap::from([
+            ("title", self.title.clone().unwrap_or_default()),
+            ("focused", self.focused.to_string()),
+            ("fullscreen", self.fullscreen.to_string()),
+            ("floating", self.floating.to_string()),
+        ])
+    }
 }
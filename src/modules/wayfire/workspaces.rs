@@ -1,4 +1,7 @@
-use std::{any::TypeId, collections::HashMap};
+use std::{
+    any::TypeId,
+    collections::{BTreeMap, HashMap},
+};
 
 use bar_rs_derive::Builder;
 use handlebars::Handlebars;
@@ -100,4 +103,11 @@ impl Module for WayfireWorkspaceMod {
     }
 
     impl_on_click!();
+
+    fn context(&self, _item: Option<usize>) -> BTreeMap<&'static str, String> {
+        BTreeMap::from([
+            ("x", self.active.0.to_string()),
+            ("y", self.active.1.to_string()),
+        ])
+    }
 }
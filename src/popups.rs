@@ -0,0 +1,348 @@
+use std::{any::TypeId, time::Duration};
+
+use iced::{
+    core::event::{wayland, PlatformSpecific},
+    event,
+    keyboard::{self, key::Named, Key},
+    platform_specific::shell::commands::popup::{destroy_popup, get_popup},
+    runtime::platform_specific::wayland::popup::SctkPopupSettings,
+    window::{self, Id},
+    Event, Subscription, Task,
+};
+use tokio::time::sleep;
+
+use crate::{
+    animation::Animated,
+    config::{popup_behavior::PopupBehavior, popup_config::PopupConfig, Config},
+    menu::{MenuEntry, OpenMenu},
+    Message,
+};
+
+/// How long a popup which opens on hover stays open after the pointer left it and its module
+const HOVER_DELAY: Duration = Duration::from_millis(300);
+
+/// An interaction with a module which may open or close its popup
+#[derive(Debug, Clone, Copy)]
+pub enum PopupTrigger {
+    Click,
+    /// An action like `popup:<module>`, which toggles the popup like a click
+    Action,
+    /// The pointer entered or left the module
+    Hover(bool),
+}
+
+/// Something which happened to an open popup
+#[derive(Debug, Clone, Copy)]
+pub enum PopupEvent {
+    /// The pointer entered or left the popup
+    Hover(bool),
+    /// Escape was pressed while the popup had the keyboard focus
+    Escape,
+    /// An entry of a menu was chosen
+    Close,
+    /// Open a submenu of a menu, or go back to its parent menu
+    Submenu(Option<usize>),
+    /// The popup lost the keyboard focus
+    Unfocused,
+    /// The compositor closed the popup, e.g. after a click somewhere else
+    Dismissed,
+    /// Close the popup after a delay, if nothing happened in the meantime
+    Check(u64),
+    /// Destroy the popup once it faded out
+    Destroy,
+}
+
+#[derive(Debug)]
+struct Popup {
+    module: TypeId,
+    id: Id,
+    behavior: PopupBehavior,
+    /// The opacity of the popup, which fades in and out
+    fade: Animated<f32>,
+    /// The popup config with colors faded according to `fade`
+    config: PopupConfig,
+    /// Whether the pointer is over the popup
+    hovered: bool,
+    /// Whether the pointer is over the module of the popup
+    module_hovered: bool,
+    /// Incremented by every event, so that outdated checks are ignored
+    generation: u64,
+    /// The state of the context menu, if the popup is one
+    menu: Option<OpenMenu>,
+}
+
+impl Popup {
+    fn closing(&self) -> bool {
+        *self.fade.target() == 0.
+    }
+
+    /// How long the popup stays open after the pointer left it
+    fn delay(&self) -> Option<Duration> {
+        match self.behavior.open_on_hover {
+            true => Some(HOVER_DELAY),
+            false => self.behavior.timeout,
+        }
+    }
+
+    /// Close the popup after its delay, unless the pointer comes back
+    fn check_later(&mut self) -> Task<Message> {
+        self.generation += 1;
+        let Some(delay) = self.delay() else {
+            return Task::none();
+        };
+        let (id, generation) = (self.id, self.generation);
+        Task::perform(sleep(delay), move |_| {
+            Message::PopupEvent(id, PopupEvent::Check(generation))
+        })
+    }
+
+    /// Fade the popup out and destroy it afterwards
+    fn close(&mut self, config: &Config) -> Task<Message> {
+        self.generation += 1;
+        self.fade.set(0.);
+        let id = self.id;
+        Task::perform(sleep(config.animation.duration()), move |_| {
+            Message::PopupEvent(id, PopupEvent::Destroy)
+        })
+    }
+}
+
+/// The popups which are open right now. Opening a popup closes the others, unless they are
+/// `pinned`.
+#[derive(Debug, Default)]
+pub struct Popups {
+    open: Vec<Popup>,
+}
+
+impl Popups {
+    /// Open or close the popup of a module after it was clicked or hovered. `settings` creates
+    /// the settings of a new popup with the given id.
+    pub fn trigger<F>(
+        &mut self,
+        module: TypeId,
+        trigger: PopupTrigger,
+        behavior: PopupBehavior,
+        settings: F,
+        config: &Config,
+    ) -> Task<Message>
+    where
+        F: FnOnce(Id) -> SctkPopupSettings,
+    {
+        let popup = self
+            .open
+            .iter_mut()
+            .find(|p| p.module == module && p.menu.is_none());
+        match (trigger, popup) {
+            (PopupTrigger::Hover(hovered), Some(popup)) => {
+                popup.module_hovered = hovered;
+                match hovered {
+                    // Opening the popup again while it fades out
+                    true if popup.behavior.open_on_hover && popup.closing() => {
+                        popup.generation += 1;
+                        popup.fade.set(1.);
+                        Task::none()
+                    }
+                    true => Task::none(),
+                    false => popup.check_later(),
+                }
+            }
+            (PopupTrigger::Hover(true), None) if behavior.open_on_hover => {
+                self.open(module, behavior, true, None, settings, config)
+            }
+            (PopupTrigger::Hover(_), None) => Task::none(),
+            (_, Some(popup)) if popup.closing() => {
+                popup.generation += 1;
+                popup.fade.set(1.);
+                Task::none()
+            }
+            (_, Some(popup)) => popup.close(config),
+            // The pointer is over the module if it was clicked
+            (trigger, None) => {
+                let clicked = matches!(trigger, PopupTrigger::Click);
+                self.open(module, behavior, clicked, None, settings, config)
+            }
+        }
+    }
+
+    /// Open the context menu of a module with the entries it contributes, or close it if it is
+    /// open already
+    pub fn toggle_menu<F>(
+        &mut self,
+        module: TypeId,
+        entries: Vec<MenuEntry>,
+        settings: F,
+        config: &Config,
+    ) -> Task<Message>
+    where
+        F: FnOnce(Id) -> SctkPopupSettings,
+    {
+        match self
+            .open
+            .iter_mut()
+            .find(|p| p.module == module && p.menu.is_some())
+        {
+            Some(popup) if !popup.closing() => popup.close(config),
+            Some(popup) => {
+                popup.generation += 1;
+                popup.fade.set(1.);
+                Task::none()
+            }
+            None => {
+                let menu = Some(OpenMenu::new(entries));
+                self.open(
+                    module,
+                    PopupBehavior::default(),
+                    true,
+                    menu,
+                    settings,
+                    config,
+                )
+            }
+        }
+    }
+
+    fn open<F>(
+        &mut self,
+        module: TypeId,
+        behavior: PopupBehavior,
+        module_hovered: bool,
+        menu: Option<OpenMenu>,
+        settings: F,
+        config: &Config,
+    ) -> Task<Message>
+    where
+        F: FnOnce(Id) -> SctkPopupSettings,
+    {
+        // Other popups which aren't pinned are replaced right away
+        let replaced = self
+            .open
+            .iter()
+            .filter(|p| !p.behavior.pinned)
+            .map(|p| destroy_popup(p.id))
+            .collect::<Vec<_>>();
+        self.open.retain(|p| p.behavior.pinned);
+
+        let id = Id::unique();
+        let mut fade = Animated::new(0.);
+        fade.configure(&config.animation);
+        fade.set(1.);
+        let mut popup = Popup {
+            module,
+            id,
+            behavior,
+            fade,
+            config: config.popup_config.faded(0.),
+            hovered: false,
+            module_hovered,
+            generation: 0,
+            menu,
+        };
+        let check = match module_hovered {
+            true => Task::none(),
+            false => popup.check_later(),
+        };
+        self.open.push(popup);
+        Task::batch(replaced)
+            .chain(get_popup(SctkPopupSettings {
+                grab: behavior.grab(),
+                ..settings(id)
+            }))
+            .chain(check)
+    }
+
+    /// Handle an event of the popup with the given id
+    pub fn handle(&mut self, id: Id, event: PopupEvent, config: &Config) -> Task<Message> {
+        let Some(i) = self.open.iter().position(|p| p.id == id) else {
+            return Task::none();
+        };
+        let popup = &mut self.open[i];
+        match event {
+            PopupEvent::Hover(hovered) => {
+                popup.hovered = hovered;
+                match hovered {
+                    true => {
+                        popup.generation += 1;
+                        Task::none()
+                    }
+                    false => popup.check_later(),
+                }
+            }
+            PopupEvent::Submenu(submenu) => {
+                if let Some(menu) = &mut popup.menu {
+                    menu.navigate(submenu);
+                }
+                Task::none()
+            }
+            PopupEvent::Check(generation) if generation != popup.generation => Task::none(),
+            PopupEvent::Check(_) if popup.hovered || popup.module_hovered => Task::none(),
+            PopupEvent::Unfocused if !popup.behavior.close_on_unfocus => Task::none(),
+            PopupEvent::Escape
+            | PopupEvent::Close
+            | PopupEvent::Unfocused
+            | PopupEvent::Check(_) => match popup.closing() {
+                true => Task::none(),
+                false => popup.close(config),
+            },
+            PopupEvent::Dismissed => {
+                self.open.remove(i);
+                destroy_popup(id)
+            }
+            // The popup might have been opened again in the meantime
+            PopupEvent::Destroy if popup.closing() => {
+                self.open.remove(i);
+                destroy_popup(id)
+            }
+            PopupEvent::Destroy => Task::none(),
+        }
+    }
+
+    /// Destroy all popups immediately, e.g. when the bar is hidden
+    pub fn close_all(&mut self) -> Task<Message> {
+        Task::batch(self.open.drain(..).map(|p| destroy_popup(p.id)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.open.is_empty()
+    }
+
+    pub fn animating(&self) -> bool {
+        self.open.iter().any(|p| p.fade.is_animating())
+    }
+
+    /// Apply the current opacity of every popup to its colors
+    pub fn refresh(&mut self, config: &PopupConfig) {
+        for popup in &mut self.open {
+            popup.config = config.faded(popup.fade.value());
+        }
+    }
+
+    /// The module of the popup with the given id, its faded config and its menu, if it is one
+    pub fn get(&self, id: Id) -> Option<(TypeId, &PopupConfig, Option<&OpenMenu>)> {
+        self.open
+            .iter()
+            .find(|p| p.id == id)
+            .map(|p| (p.module, &p.config, p.menu.as_ref()))
+    }
+
+    /// Events of the open popups which may close them
+    pub fn subscription(&self) -> Option<Subscription<Message>> {
+        (!self.is_empty()).then(|| event::listen_with(popup_event))
+    }
+}
+
+fn popup_event(event: Event, _: event::Status, id: Id) -> Option<Message> {
+    let event = match event {
+        Event::Keyboard(keyboard::Event::KeyPressed {
+            key: Key::Named(Named::Escape),
+            ..
+        }) => PopupEvent::Escape,
+        Event::Window(window::Event::Unfocused) => PopupEvent::Unfocused,
+        Event::PlatformSpecific(PlatformSpecific::Wayland(wayland::Event::Popup(
+            wayland::PopupEvent::Done,
+            _,
+            id,
+        ))) => return Some(Message::PopupEvent(id, PopupEvent::Dismissed)),
+        _ => return None,
+    };
+    Some(Message::PopupEvent(id, event))
+}
@@ -71,6 +71,18 @@ impl Registry {
         self.modules.get(&id).unwrap().as_ref()
     }
 
+    pub fn get_module_by_id_mut(&mut self, id: TypeId) -> &mut dyn Module {
+        self.modules.get_mut(&id).unwrap().as_mut()
+    }
+
+    /// Get the id of the module with the given name, which may also be a resolver
+    pub fn resolve(&self, name: &str, config: Option<&Config>) -> Option<TypeId> {
+        self.module_names
+            .get(name)
+            .copied()
+            .or_else(|| self.resolvers.get(name).and_then(|f| f(config)))
+    }
+
     pub fn get_module<T: Module>(&self) -> &T {
         self.try_get_module().unwrap()
     }
@@ -96,10 +108,7 @@ impl Registry {
         I: Iterator<Item = &'a String>,
     {
         enabled.filter_map(|id| {
-            self.module_names
-                .get(id)
-                .copied()
-                .or_else(|| self.resolvers.get(id).and_then(|f| f(Some(config))))
+            self.resolve(id, Some(config))
                 .and_then(|id| self.modules.get(&id))
         })
     }
@@ -0,0 +1,101 @@
+use std::f32::consts::FRAC_PI_2;
+
+use iced::{
+    alignment::{Horizontal, Vertical},
+    core::text::{self, Paragraph},
+    mouse,
+    widget::{
+        canvas::{self, Frame, Geometry},
+        text::{LineHeight, Shaping, Wrapping},
+        Canvas,
+    },
+    Color, Element, Font,
+    Length::Fill,
+    Point, Rectangle, Renderer, Size, Theme, Vector,
+};
+
+use crate::{
+    config::{anchor::BarAnchor, marquee_config::MarqueeConfig},
+    fill::FillExt,
+    marquee::Marquee,
+};
+
+/// Text which is rotated by 90 degrees if `rotate` is enabled and the bar is vertical, so that
+/// long text reads along the bar. Otherwise it scrolls if the `marquee` is enabled and the text
+/// is too long. Rotated text doesn't scroll.
+pub fn bar_text<'a, Message: 'a>(
+    content: String,
+    size: f32,
+    color: Color,
+    anchor: &BarAnchor,
+    rotate: bool,
+    marquee: MarqueeConfig,
+) -> Element<'a, Message> {
+    if !(rotate && anchor.vertical()) {
+        if marquee.enabled {
+            return Marquee::new(content, size, color, marquee)
+                .vertical(anchor.vertical())
+                .into();
+        }
+        return iced::widget::text(content)
+            .size(size)
+            .color(color)
+            .fill(anchor)
+            .into();
+    }
+    let bounds = <Renderer as text::Renderer>::Paragraph::with_text(text::Text {
+        content: &content,
+        bounds: Size::INFINITY,
+        size: size.into(),
+        line_height: LineHeight::default(),
+        font: Font::DEFAULT,
+        horizontal_alignment: Horizontal::Left,
+        vertical_alignment: Vertical::Top,
+        shaping: Shaping::Advanced,
+        wrapping: Wrapping::None,
+    })
+    .min_bounds();
+    // The text is as long as the canvas is high
+    Canvas::new(RotatedText {
+        content,
+        size,
+        color,
+    })
+    .width(Fill)
+    .height(bounds.width)
+    .into()
+}
+
+struct RotatedText {
+    content: String,
+    size: f32,
+    color: Color,
+}
+
+impl<Message> canvas::Program<Message> for RotatedText {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        frame.translate(Vector::new(bounds.width / 2., bounds.height / 2.));
+        frame.rotate(FRAC_PI_2);
+        frame.fill_text(canvas::Text {
+            content: self.content.clone(),
+            position: Point::ORIGIN,
+            color: self.color,
+            size: self.size.into(),
+            horizontal_alignment: Horizontal::Center,
+            vertical_alignment: Vertical::Center,
+            shaping: Shaping::Advanced,
+            ..Default::default()
+        });
+        vec![frame.into_geometry()]
+    }
+}
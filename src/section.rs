@@ -0,0 +1,382 @@
+use iced::{
+    core::{
+        event, layout, mouse, overlay, renderer,
+        widget::{tree, Operation, Tree},
+        Clipboard, Layout, Shell, Widget,
+    },
+    Alignment, Element, Event, Length, Rectangle, Size, Vector,
+};
+
+use crate::config::anchor::BarAnchor;
+
+/// What a [Section] does with modules which don't fit into it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// Cut off the modules at the end of the section
+    #[default]
+    Clip,
+    /// Give every module less space, in proportion to its size
+    Shrink,
+    /// Hide the modules with the lowest priority until the rest fits
+    Hide,
+}
+
+/// A list of modules which takes a share of the bar, according to its weight. Unlike a row or
+/// column, its modules never leave its bounds, they are handled according to its [Overflow].
+pub struct Section<'a, Message, Theme = iced::Theme, Renderer = iced::Renderer> {
+    children: Vec<Element<'a, Message, Theme, Renderer>>,
+    /// The priority of each child, see [Overflow::Hide]
+    priorities: Vec<i32>,
+    vertical: bool,
+    align: Alignment,
+    weight: u16,
+    spacing: f32,
+    overflow: Overflow,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Children which are hidden because of [Overflow::Hide]
+    hidden: Vec<bool>,
+}
+
+impl<'a, Message, Theme, Renderer> Section<'a, Message, Theme, Renderer> {
+    /// Creates a new [Section] from modules and their priority
+    pub fn new(
+        anchor: &BarAnchor,
+        children: impl IntoIterator<Item = (Element<'a, Message, Theme, Renderer>, i32)>,
+    ) -> Self {
+        let (children, priorities) = children.into_iter().unzip();
+        Self {
+            children,
+            priorities,
+            vertical: anchor.vertical(),
+            align: Alignment::Start,
+            weight: 1,
+            spacing: 0.,
+            overflow: Overflow::default(),
+        }
+    }
+
+    pub fn align(mut self, align: Alignment) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// The share of the bar this section takes, `0` makes it as small as its modules
+    pub fn weight(mut self, weight: u16) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// The size along the bar
+    fn main(&self, size: Size) -> f32 {
+        match self.vertical {
+            true => size.height,
+            false => size.width,
+        }
+    }
+
+    /// The size across the bar
+    fn cross(&self, size: Size) -> f32 {
+        match self.vertical {
+            true => size.width,
+            false => size.height,
+        }
+    }
+
+    fn size_of(&self, main: f32, cross: f32) -> Size {
+        match self.vertical {
+            true => Size::new(cross, main),
+            false => Size::new(main, cross),
+        }
+    }
+
+    /// The space the visible children take, including the spacing between them
+    fn content_size(&self, nodes: &[layout::Node], hidden: &[bool]) -> f32 {
+        let visible = nodes
+            .iter()
+            .zip(hidden)
+            .filter(|(_, hidden)| !**hidden)
+            .map(|(node, _)| self.main(node.size()))
+            .collect::<Vec<_>>();
+        visible.iter().sum::<f32>() + self.spacing * visible.len().saturating_sub(1) as f32
+    }
+
+    /// The children which are not hidden, with their tree and layout
+    fn visible_children<'b>(
+        &'b self,
+        trees: &'b [Tree],
+        layout: Layout<'b>,
+        hidden: &'b [bool],
+    ) -> impl Iterator<
+        Item = (
+            &'b Element<'a, Message, Theme, Renderer>,
+            &'b Tree,
+            Layout<'b>,
+        ),
+    > {
+        self.children
+            .iter()
+            .zip(trees)
+            .zip(layout.children())
+            .zip(hidden)
+            .filter(|(_, hidden)| !**hidden)
+            .map(|(((child, tree), layout), _)| (child, tree, layout))
+    }
+}
+
+impl<'a, Message, Theme, Renderer> Widget<Message, Theme, Renderer>
+    for Section<'a, Message, Theme, Renderer>
+where
+    Renderer: iced::core::Renderer,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        self.children.iter().map(Tree::new).collect()
+    }
+
+    fn diff(&mut self, tree: &mut Tree) {
+        tree.diff_children(&mut self.children);
+    }
+
+    fn size(&self) -> Size<Length> {
+        let main = match self.weight {
+            0 => Length::Shrink,
+            weight => Length::FillPortion(weight),
+        };
+        match self.vertical {
+            true => Size::new(Length::Fill, main),
+            false => Size::new(main, Length::Fill),
+        }
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        let size = self.size();
+        let limits = limits.width(size.width).height(size.height);
+        let available = self.main(limits.max());
+        let cross = self.cross(limits.max());
+        let Tree {
+            state, children, ..
+        } = tree;
+        let state = state.downcast_mut::<State>();
+        let mut layout_children = |max_main: &dyn Fn(usize) -> f32| {
+            self.children
+                .iter()
+                .zip(children.iter_mut())
+                .enumerate()
+                .map(|(i, (child, tree))| {
+                    let limits = layout::Limits::new(Size::ZERO, self.size_of(max_main(i), cross));
+                    child.as_widget().layout(tree, renderer, &limits)
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut nodes = layout_children(&|_| available);
+        let mut hidden = vec![false; nodes.len()];
+
+        if self.content_size(&nodes, &hidden) > available {
+            match self.overflow {
+                Overflow::Clip => {}
+                Overflow::Shrink => {
+                    let spacing = self.spacing * nodes.len().saturating_sub(1) as f32;
+                    let total = nodes.iter().map(|n| self.main(n.size())).sum::<f32>();
+                    let scale = (available - spacing).max(0.) / total.max(f32::EPSILON);
+                    let sizes = nodes
+                        .iter()
+                        .map(|n| self.main(n.size()) * scale)
+                        .collect::<Vec<_>>();
+                    nodes = layout_children(&|i| sizes[i]);
+                }
+                Overflow::Hide => {
+                    // Lower priorities are hidden first, modules at the end of the section first
+                    // if the priorities are equal
+                    let mut order = (0..nodes.len()).collect::<Vec<_>>();
+                    order.sort_by_key(|&i| (self.priorities[i], std::cmp::Reverse(i)));
+                    for i in order {
+                        if self.content_size(&nodes, &hidden) <= available {
+                            break;
+                        }
+                        hidden[i] = true;
+                    }
+                }
+            }
+        }
+
+        let content = self.content_size(&nodes, &hidden);
+        // Align within the size of the section itself, which is just the content if it shrinks
+        let size = limits.resolve(size.width, size.height, self.size_of(content, cross));
+        let free = (self.main(size) - content).max(0.);
+        let mut position = match self.align {
+            Alignment::Start => 0.,
+            Alignment::Center => free / 2.,
+            Alignment::End => free,
+        };
+        let nodes = nodes
+            .into_iter()
+            .zip(&hidden)
+            .map(|(node, hidden)| {
+                if *hidden {
+                    return layout::Node::new(Size::ZERO);
+                }
+                let offset = (cross - self.cross(node.size())).max(0.) / 2.;
+                let node = node.move_to(match self.vertical {
+                    true => (offset, position),
+                    false => (position, offset),
+                });
+                position += self.main(node.size()) + self.spacing;
+                node
+            })
+            .collect();
+        state.hidden = hidden;
+
+        layout::Node::with_children(size, nodes)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation,
+    ) {
+        operation.container(None, layout.bounds(), &mut |operation| {
+            self.children
+                .iter()
+                .zip(&mut tree.children)
+                .zip(layout.children())
+                .for_each(|((child, tree), layout)| {
+                    child.as_widget().operate(tree, layout, renderer, operation);
+                });
+        });
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        // Modules are only interactive inside of the section
+        let cursor = match cursor.is_over(layout.bounds()) {
+            true => cursor,
+            false => mouse::Cursor::Unavailable,
+        };
+        let hidden = &tree.state.downcast_ref::<State>().hidden;
+        self.children
+            .iter_mut()
+            .zip(&mut tree.children)
+            .zip(layout.children())
+            .zip(hidden)
+            .filter(|(_, hidden)| !**hidden)
+            .map(|(((child, tree), layout), _)| {
+                child.as_widget_mut().on_event(
+                    tree,
+                    event.clone(),
+                    layout,
+                    cursor,
+                    renderer,
+                    clipboard,
+                    shell,
+                    viewport,
+                )
+            })
+            .fold(event::Status::Ignored, event::Status::merge)
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        let Some(bounds) = layout.bounds().intersection(viewport) else {
+            return;
+        };
+        let hidden = &tree.state.downcast_ref::<State>().hidden;
+        renderer.with_layer(bounds, |renderer| {
+            for (child, tree, layout) in self.visible_children(&tree.children, layout, hidden) {
+                let Some(viewport) = layout.bounds().intersection(&bounds) else {
+                    continue;
+                };
+                // Shrunk modules may be larger than the space they got
+                renderer.with_layer(viewport, |renderer| {
+                    child
+                        .as_widget()
+                        .draw(tree, renderer, theme, style, layout, cursor, &viewport)
+                });
+            }
+        });
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let hidden = &tree.state.downcast_ref::<State>().hidden;
+        self.visible_children(&tree.children, layout, hidden)
+            .map(|(child, tree, layout)| {
+                child
+                    .as_widget()
+                    .mouse_interaction(tree, layout, cursor, viewport, renderer)
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        translation: Vector,
+    ) -> Option<overlay::Element<'b, Message, Theme, Renderer>> {
+        overlay::from_children(&mut self.children, tree, layout, renderer, translation)
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<Section<'a, Message, Theme, Renderer>>
+    for Element<'a, Message, Theme, Renderer>
+where
+    Message: 'a,
+    Theme: 'a,
+    Renderer: iced::core::Renderer + 'a,
+{
+    fn from(section: Section<'a, Message, Theme, Renderer>) -> Self {
+        Self::new(section)
+    }
+}
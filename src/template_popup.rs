@@ -0,0 +1,161 @@
+use std::{any::TypeId, collections::HashMap};
+
+use handlebars::{Handlebars, JsonValue};
+use iced::{
+    widget::{
+        button::{Status, Style as ButtonStyle},
+        column, container,
+        container::Style,
+        row, scrollable, text,
+    },
+    Background, Border, Element,
+};
+
+use crate::{
+    actions::parse_action,
+    button::button,
+    config::{
+        anchor::BarAnchor,
+        popup_config::{PopupConfig, PopupConfigOverride},
+    },
+    fill::FillExt,
+    helpers::UnEscapeString,
+    modules::{align_popup, Action, ActionContext, Module},
+    variables::module_values,
+    Message,
+};
+
+/// A popup which is defined by the `template` and `button_<n>` options of the
+/// `[module_popup:<name>]` section of a module. It replaces the popup of the module, if it has
+/// one.
+#[derive(Debug)]
+pub struct TemplatePopup {
+    config: PopupConfigOverride,
+    /// The labels and actions of the buttons below the text, in the order of their numbers
+    buttons: Vec<(String, Box<dyn Action>)>,
+}
+
+fn template_name(module: &str) -> String {
+    format!("popup:{module}")
+}
+
+impl TemplatePopup {
+    /// Read the popup of a module and register its template. Returns `None` if the module
+    /// doesn't have a valid `template`.
+    pub fn read(
+        module: &str,
+        config: &HashMap<String, Option<String>>,
+        templates: &mut Handlebars,
+    ) -> Option<Self> {
+        let template = config.get("template").unescape()?;
+        templates
+            .register_template_string(&template_name(module), template)
+            .map_err(|e| eprintln!("Failed to parse the popup template of {module}: {e}"))
+            .ok()?;
+        let mut buttons = config
+            .iter()
+            .filter_map(|(key, value)| {
+                let number: u32 = key.strip_prefix("button_")?.parse().ok()?;
+                let Some((label, action)) = value.as_ref()?.split_once('|') else {
+                    eprintln!("Invalid {key} of the popup of {module}: expected label | action");
+                    return None;
+                };
+                Some((
+                    number,
+                    label.trim().to_string(),
+                    parse_action(action.trim()),
+                ))
+            })
+            .collect::<Vec<_>>();
+        buttons.sort_by_key(|(number, ..)| *number);
+        let mut popup_config = PopupConfigOverride::default();
+        popup_config.update(config);
+        Some(Self {
+            config: popup_config,
+            buttons: buttons
+                .into_iter()
+                .map(|(_, label, action)| (label, action))
+                .collect(),
+        })
+    }
+
+    pub fn size(&self, config: &PopupConfig) -> (i32, i32) {
+        (
+            self.config.width.unwrap_or(config.width),
+            self.config.height.unwrap_or(config.height),
+        )
+    }
+
+    /// The view of the popup of `module`, which has the given id. `state` contains the values
+    /// of all modules, see [module_values].
+    pub fn view<'a>(
+        &'a self,
+        module: &dyn Module,
+        id: TypeId,
+        config: &'a PopupConfig,
+        anchor: &BarAnchor,
+        state: &JsonValue,
+        templates: &Handlebars,
+    ) -> Element<'a, Message> {
+        let name = module.name();
+        let content = templates
+            .render(&template_name(&name), &module_values(state, module))
+            .map_err(|e| eprintln!("Failed to render the popup of {name}: {e}"))
+            .unwrap_or_default();
+        let font_size = self.config.font_size.unwrap_or(config.font_size);
+        let text_color = self.config.text_color.unwrap_or(config.text_color);
+        let border = self.config.border.unwrap_or(config.border);
+        let spacing = self.config.spacing.unwrap_or(config.spacing);
+
+        let mut vars = module.context(None);
+        vars.insert("module", name);
+        let buttons = self.buttons.iter().map(|(label, action)| {
+            let vars = vars.clone();
+            button(text(label.as_str()).size(font_size).color(text_color))
+                .padding([4, 10])
+                .on_event_try(move |event, layout, _, _, _| {
+                    event.is_left_click().then(|| {
+                        action.as_message(&ActionContext {
+                            module: id,
+                            bounds: layout.bounds(),
+                            vars: vars.clone(),
+                        })
+                    })
+                })
+                .style(move |_, status| ButtonStyle {
+                    background: matches!(status, Status::Hovered | Status::Pressed)
+                        .then(|| Background::Color(text_color.scale_alpha(0.2))),
+                    text_color,
+                    border: Border {
+                        radius: border.radius,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .into()
+        });
+
+        let popup = container(
+            column![
+                scrollable(
+                    container(text(content).size(font_size).color(text_color))
+                        .padding(self.config.text_margin.unwrap_or(config.text_margin))
+                ),
+                row(buttons).spacing(spacing),
+            ]
+            .spacing(spacing),
+        )
+        .padding(self.config.padding.unwrap_or(config.padding))
+        .style(move |_| Style {
+            background: Some(self.config.background.unwrap_or(config.background)),
+            border,
+            ..Default::default()
+        })
+        .fill_maybe(
+            self.config
+                .fill_content_to_size
+                .unwrap_or(config.fill_content_to_size),
+        );
+        align_popup(popup, anchor)
+    }
+}
@@ -1,10 +1,16 @@
+use handlebars::{Handlebars, JsonValue};
 use iced::widget::container;
-use iced::widget::tooltip::Position;
+use iced::Theme;
 use iced::{
     widget::{container::Style, Tooltip},
     Element,
 };
-use iced::{Background, Border, Color, Theme};
+
+use crate::{
+    config::{anchor::BarAnchor, tooltip_config::TooltipConfig, Config},
+    modules::Module,
+    variables::module_values,
+};
 
 pub trait ElementExt<'a, Message, Renderer>
 where
@@ -14,10 +20,14 @@ where
     fn tooltip(
         self,
         tooltip: impl Into<Element<'a, Message, Theme, Renderer>>,
+        config: &TooltipConfig,
+        anchor: &BarAnchor,
     ) -> Tooltip<'a, Message, Theme, Renderer>;
     fn tooltip_maybe(
         self,
         tooltip: Option<impl Into<Element<'a, Message, Theme, Renderer>>>,
+        config: &TooltipConfig,
+        anchor: &BarAnchor,
     ) -> Element<'a, Message, Theme, Renderer>;
 }
 
@@ -30,29 +40,64 @@ where
     fn tooltip(
         self,
         tooltip: impl Into<Element<'a, Message, Theme, Renderer>>,
+        config: &TooltipConfig,
+        anchor: &BarAnchor,
     ) -> Tooltip<'a, Message, Theme, Renderer> {
+        let config = *config;
         iced::widget::tooltip(
             self,
-            container(tooltip).padding([2, 10]).style(|_| Style {
-                text_color: Some(Color::WHITE),
-                background: Some(Background::Color(Color::BLACK)),
-                border: Border {
-                    color: Color::WHITE,
-                    width: 1.,
-                    radius: 5_f32.into(),
-                },
-                ..Default::default()
-            }),
-            Position::Bottom,
+            container(tooltip)
+                .padding(config.padding)
+                .style(move |_| Style {
+                    text_color: Some(config.text_color),
+                    background: Some(config.background),
+                    border: config.border,
+                    ..Default::default()
+                }),
+            config.position(anchor),
         )
+        .gap(config.gap)
     }
     fn tooltip_maybe(
         self,
         tooltip: Option<impl Into<Element<'a, Message, Theme, Renderer>>>,
+        config: &TooltipConfig,
+        anchor: &BarAnchor,
     ) -> Element<'a, Message, Theme, Renderer> {
         match tooltip {
-            Some(t) => self.tooltip(t).into(),
+            Some(t) => self.tooltip(t, config, anchor).into(),
             None => self.into(),
         }
     }
 }
+
+fn template_name(module: &str) -> String {
+    format!("tooltip:{module}")
+}
+
+/// Register the `tooltip` template of a module. Returns whether the template is valid.
+pub fn register_tooltip(module: &str, tooltip: String, templates: &mut Handlebars) -> bool {
+    templates
+        .register_template_string(&template_name(module), tooltip)
+        .map_err(|e| eprintln!("Failed to parse the tooltip of {module}: {e}"))
+        .is_ok()
+}
+
+/// The tooltip of a module, if it has one. `state` contains the values of all modules, see
+/// [module_values].
+pub fn module_tooltip(
+    module: &dyn Module,
+    state: &JsonValue,
+    config: &Config,
+    templates: &Handlebars,
+) -> Option<String> {
+    let name = module.name();
+    if !config.tooltips.contains(&name) {
+        return None;
+    }
+    templates
+        .render(&template_name(&name), &module_values(state, module))
+        .map_err(|e| eprintln!("Failed to render the tooltip of {name}: {e}"))
+        .ok()
+        .filter(|tooltip| !tooltip.trim().is_empty())
+}
@@ -0,0 +1,136 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, JsonValue, RenderContext, RenderError,
+    RenderErrorReason, ScopedJson,
+};
+
+use serde_json::Map;
+
+use crate::{config::Config, modules::Module, registry::Registry};
+
+/// Values published by the enabled modules (their [context](crate::modules::Module::context)),
+/// which can be read by every template and `show_when` rule. Keys are the module name and the
+/// name of the value, e.g. `cpu.total` or `niri.window.title`.
+#[derive(Debug, Default, Clone)]
+pub struct Variables(Arc<RwLock<BTreeMap<String, BTreeMap<&'static str, String>>>>);
+
+impl Variables {
+    /// Replace the values with the current state of the enabled modules
+    pub fn publish(&self, registry: &Registry, config: &Config) {
+        let values = registry
+            .get_modules(config.enabled_modules.get_all(), config)
+            .map(|m| (m.name(), m.context(None)))
+            .collect();
+        *self.0.write().unwrap() = values;
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        let (module, name) = key.rsplit_once('.')?;
+        self.0.read().unwrap().get(module)?.get(name).cloned()
+    }
+
+    /// All values as `key = value`, one per line
+    pub fn dump(&self) -> String {
+        self.0
+            .read()
+            .unwrap()
+            .iter()
+            .flat_map(|(module, values)| {
+                values
+                    .iter()
+                    .map(move |(name, value)| format!("{module}.{name} = {value}\n"))
+            })
+            .collect()
+    }
+
+    /// The values of every module by its name, so that templates can use `cpu.total`. Names
+    /// with a dot are nested as well, so that `niri.window.title` works too.
+    pub fn json(&self) -> JsonValue {
+        let mut json = Map::new();
+        for (module, values) in self.0.read().unwrap().iter() {
+            json.insert(module.clone(), json_values(values));
+            let Some((parents, name)) = module.rsplit_once('.') else {
+                continue;
+            };
+            let parent = parents.split('.').try_fold(&mut json, |obj, key| {
+                match obj
+                    .entry(key)
+                    .or_insert_with(|| JsonValue::Object(Map::new()))
+                {
+                    JsonValue::Object(obj) => Some(obj),
+                    _ => None,
+                }
+            });
+            if let Some(parent) = parent {
+                parent.insert(name.to_string(), json_values(values));
+            }
+        }
+        JsonValue::Object(json)
+    }
+
+    /// The `var` template helper, e.g. `{{var "battery.capacity"}}`
+    pub fn helper(&self) -> VarHelper {
+        VarHelper(self.clone())
+    }
+}
+
+/// The values of all modules (see [Variables::json]) together with the values of the given module,
+/// which can be used without its name, e.g. `total` instead of `cpu.total`
+pub fn module_values(state: &JsonValue, module: &dyn Module) -> JsonValue {
+    let mut values = match state {
+        JsonValue::Object(_) => state.clone(),
+        _ => JsonValue::Object(Default::default()),
+    };
+    if let (JsonValue::Object(values), JsonValue::Object(own)) =
+        (&mut values, json_values(&module.context(None)))
+    {
+        values.extend(own);
+    }
+    values
+}
+
+/// Module context values are strings, convert them so that they can be compared as numbers
+fn json_values(ctx: &BTreeMap<&'static str, String>) -> JsonValue {
+    ctx.iter()
+        .map(|(key, value)| (*key, json_value(value)))
+        .collect()
+}
+
+fn json_value(value: &str) -> JsonValue {
+    if let Ok(number) = value.parse::<i64>() {
+        // Keep integers as integers, so that `87` isn't rendered as `87.0`
+        return number.into();
+    }
+    match (value, value.parse::<f64>()) {
+        ("true", _) => JsonValue::Bool(true),
+        ("false", _) => JsonValue::Bool(false),
+        (_, Ok(number)) if number.is_finite() => number.into(),
+        _ => JsonValue::String(value.to_string()),
+    }
+}
+
+pub struct VarHelper(Variables);
+
+impl HelperDef for VarHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let key = h
+            .param(0)
+            .and_then(|key| key.value().as_str())
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("var", 0))?;
+        Ok(self
+            .0
+            .get(key)
+            .map_or(JsonValue::Null, |value| json_value(&value))
+            .into())
+    }
+}